@@ -1,17 +1,29 @@
-use std::time::Duration;
+use std::{fmt, time::Duration};
 
+use crossterm::event::{MouseButton, MouseEventKind};
 use mpris::{LoopStatus, Player};
-use serde::{de, Deserialize};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize,
+};
 
 use tokio::sync::oneshot;
 
 use crate::{
     fum::Fum,
-    regexes::{BACKWARD_RE, FORWARD_RE, VAR_SET_RE, VAR_TOGGLE_RE},
+    regexes::{
+        BACKWARD_RE, EXEC_RE, FORWARD_RE, RATE_DOWN_RE, RATE_SET_RE, RATE_UP_RE, SELECT_PLAYER_RE,
+        SWITCH_LAYOUT_RE, VAR_SET_RE, VAR_TOGGLE_RE, VOLUME_DOWN_RE, VOLUME_SET_RE, VOLUME_UP_RE,
+    },
+    text::replace_args,
+    utils,
     youtube::{Rating, YouTubeAction},
     FumResult,
 };
 
+/// Slowest rate the rate actions go down to, for players reporting a minimum of 0.
+const MIN_RATE: f64 = 0.05;
+
 macro_rules! if_player {
     ($player:expr, $callback:expr) => {
         if let Some(player) = $player {
@@ -20,6 +32,74 @@ macro_rules! if_player {
     };
 }
 
+/// Like `if_player!`, but ignores errors of players that don't support the property.
+macro_rules! try_player {
+    ($player:expr, $callback:expr) => {
+        if let Some(player) = $player {
+            let _ = $callback(player);
+        }
+    };
+}
+
+/// An action and / or a command to run, written either as just an action or as
+/// `{ "action": ..., "exec": ... }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Binding {
+    Action(Action),
+    Full {
+        action: Option<Action>,
+        exec: Option<String>,
+    },
+}
+
+impl Binding {
+    pub fn run(binding: &Binding, fum: &mut Fum) -> FumResult<()> {
+        match binding {
+            Binding::Action(action) => Action::run(action, fum),
+            Binding::Full { action, exec } => {
+                if let Some(action) = action {
+                    Action::run(action, fum)?;
+                }
+
+                if let Some(exec) = exec {
+                    Action::run(&Action::Exec(exec.to_string()), fum)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Bindings for mouse events besides left click.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MouseBindings {
+    pub on_right_click: Option<Binding>,
+    pub on_middle_click: Option<Binding>,
+    pub on_scroll_up: Option<Binding>,
+    pub on_scroll_down: Option<Binding>,
+}
+
+impl MouseBindings {
+    pub fn is_empty(&self) -> bool {
+        self.on_right_click.is_none()
+            && self.on_middle_click.is_none()
+            && self.on_scroll_up.is_none()
+            && self.on_scroll_down.is_none()
+    }
+
+    pub fn get(&self, kind: MouseEventKind) -> Option<&Binding> {
+        match kind {
+            MouseEventKind::Down(MouseButton::Right) => self.on_right_click.as_ref(),
+            MouseEventKind::Down(MouseButton::Middle) => self.on_middle_click.as_ref(),
+            MouseEventKind::ScrollUp => self.on_scroll_up.as_ref(),
+            MouseEventKind::ScrollDown => self.on_scroll_down.as_ref(),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Action {
     Quit,
@@ -44,12 +124,32 @@ pub enum Action {
     Forward(i64),
     Backward(i64),
 
+    VolumeUp(i64),
+    VolumeDown(i64),
+    VolumeSet(i64),
+    MuteToggle,
+
+    RateUp(f64),
+    RateDown(f64),
+    RateSet(f64),
+    RateReset,
+
+    NextPlayer,
+    PrevPlayer,
+    SelectPlayer(String),
+
+    SwitchLayout(String),
+    CycleLayout,
+
     Toggle(String, String, String),
     Set(String, String),
 
     Upvote,
     Downvote,
     ClearVote,
+
+    Exec(String),
+    Many(Vec<Action>),
 }
 
 impl<'de> Deserialize<'de> for Action {
@@ -57,9 +157,59 @@ impl<'de> Deserialize<'de> for Action {
     where
         D: serde::Deserializer<'de>,
     {
-        let action_str: &str = Deserialize::deserialize(deserializer)?;
+        deserializer.deserialize_any(ActionVisitor)
+    }
+}
+
+/// Accepts either a single action or an array of actions to run in order.
+struct ActionVisitor;
 
+impl<'de> Visitor<'de> for ActionVisitor {
+    type Value = Action;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an action or an array of actions")
+    }
+
+    fn visit_str<E>(self, action_str: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Action::parse(action_str)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut actions = Vec::new();
+
+        while let Some(action) = seq.next_element::<Action>()? {
+            actions.push(action);
+        }
+
+        Ok(Action::Many(actions))
+    }
+}
+
+impl Action {
+    fn parse<E>(action_str: &str) -> Result<Action, E>
+    where
+        E: de::Error,
+    {
         match action_str {
+            // exec() action, checked first so the command can contain anything
+            a if EXEC_RE.is_match(a) => {
+                if let Some(captures) = EXEC_RE.captures(a) {
+                    return Ok(Action::Exec(captures[1].trim().to_string()));
+                }
+
+                Err(de::Error::custom("Invalid exec() format"))
+            }
+            "exec()" => Err(de::Error::custom(
+                "Invalid exec() format, needs command inside",
+            )),
+
             "quit()" => Ok(Action::Quit),
 
             "stop()" => Ok(Action::Stop),
@@ -79,6 +229,15 @@ impl<'de> Deserialize<'de> for Action {
             "loop_playlist()" => Ok(Action::LoopPlaylist),
             "loop_cycle()" => Ok(Action::LoopCycle),
 
+            "mute_toggle()" => Ok(Action::MuteToggle),
+
+            "rate_reset()" => Ok(Action::RateReset),
+
+            "next_player()" => Ok(Action::NextPlayer),
+            "prev_player()" => Ok(Action::PrevPlayer),
+
+            "cycle_layout()" => Ok(Action::CycleLayout),
+
             "upvote()" => Ok(Action::Upvote),
             "downvote()" => Ok(Action::Downvote),
             "clear_vote()" => Ok(Action::ClearVote),
@@ -110,12 +269,131 @@ impl<'de> Deserialize<'de> for Action {
             }
 
             // Error if forward() / backward() has no value inside
-            "forward()" => Err(de::Error::custom(format!(
-                "Invalid forward() format, needs value inside"
-            ))),
-            "backward()" => Err(de::Error::custom(format!(
-                "Invalid backward() format, needs value inside"
-            ))),
+            "forward()" => Err(de::Error::custom(
+                "Invalid forward() format, needs value inside",
+            )),
+            "backward()" => Err(de::Error::custom(
+                "Invalid backward() format, needs value inside",
+            )),
+
+            // volume_up() action
+            a if VOLUME_UP_RE.is_match(a) => {
+                if let Some(captures) = VOLUME_UP_RE.captures(a) {
+                    match captures[1].parse::<i64>() {
+                        Ok(step) => return Ok(Action::VolumeUp(step)),
+                        Err(_) => return Err(de::Error::custom("Invalid volume_up() step format")),
+                    }
+                }
+
+                Err(de::Error::custom("Invalid volume_up() format"))
+            }
+
+            // volume_down() action
+            a if VOLUME_DOWN_RE.is_match(a) => {
+                if let Some(captures) = VOLUME_DOWN_RE.captures(a) {
+                    match captures[1].parse::<i64>() {
+                        Ok(step) => return Ok(Action::VolumeDown(step)),
+                        Err(_) => {
+                            return Err(de::Error::custom("Invalid volume_down() step format"))
+                        }
+                    }
+                }
+
+                Err(de::Error::custom("Invalid volume_down() format"))
+            }
+
+            // volume_set() action
+            a if VOLUME_SET_RE.is_match(a) => {
+                if let Some(captures) = VOLUME_SET_RE.captures(a) {
+                    match captures[1].parse::<i64>() {
+                        Ok(volume) => return Ok(Action::VolumeSet(volume)),
+                        Err(_) => {
+                            return Err(de::Error::custom("Invalid volume_set() value format"))
+                        }
+                    }
+                }
+
+                Err(de::Error::custom("Invalid volume_set() format"))
+            }
+
+            // Error if volume_up() / volume_down() / volume_set() has no value inside
+            "volume_up()" => Err(de::Error::custom(
+                "Invalid volume_up() format, needs value inside",
+            )),
+            "volume_down()" => Err(de::Error::custom(
+                "Invalid volume_down() format, needs value inside",
+            )),
+            "volume_set()" => Err(de::Error::custom(
+                "Invalid volume_set() format, needs value inside",
+            )),
+
+            // rate_up() action
+            a if RATE_UP_RE.is_match(a) => {
+                if let Some(captures) = RATE_UP_RE.captures(a) {
+                    match captures[1].parse::<f64>() {
+                        Ok(step) => return Ok(Action::RateUp(step)),
+                        Err(_) => return Err(de::Error::custom("Invalid rate_up() step format")),
+                    }
+                }
+
+                Err(de::Error::custom("Invalid rate_up() format"))
+            }
+
+            // rate_down() action
+            a if RATE_DOWN_RE.is_match(a) => {
+                if let Some(captures) = RATE_DOWN_RE.captures(a) {
+                    match captures[1].parse::<f64>() {
+                        Ok(step) => return Ok(Action::RateDown(step)),
+                        Err(_) => return Err(de::Error::custom("Invalid rate_down() step format")),
+                    }
+                }
+
+                Err(de::Error::custom("Invalid rate_down() format"))
+            }
+
+            // rate_set() action
+            a if RATE_SET_RE.is_match(a) => {
+                if let Some(captures) = RATE_SET_RE.captures(a) {
+                    match captures[1].parse::<f64>() {
+                        Ok(rate) => return Ok(Action::RateSet(rate)),
+                        Err(_) => return Err(de::Error::custom("Invalid rate_set() value format")),
+                    }
+                }
+
+                Err(de::Error::custom("Invalid rate_set() format"))
+            }
+
+            // Error if rate_up() / rate_down() / rate_set() has no value inside
+            "rate_up()" => Err(de::Error::custom(
+                "Invalid rate_up() format, needs value inside",
+            )),
+            "rate_down()" => Err(de::Error::custom(
+                "Invalid rate_down() format, needs value inside",
+            )),
+            "rate_set()" => Err(de::Error::custom(
+                "Invalid rate_set() format, needs value inside",
+            )),
+
+            // select_player() action
+            a if SELECT_PLAYER_RE.is_match(a) => {
+                if let Some(captures) = SELECT_PLAYER_RE.captures(a) {
+                    return Ok(Action::SelectPlayer(captures[1].trim().to_string()));
+                }
+
+                Err(de::Error::custom("Invalid select_player() format"))
+            }
+
+            // switch_layout() action
+            a if SWITCH_LAYOUT_RE.is_match(a) => {
+                if let Some(captures) = SWITCH_LAYOUT_RE.captures(a) {
+                    return Ok(Action::SwitchLayout(captures[1].trim().to_string()));
+                }
+
+                Err(de::Error::custom("Invalid switch_layout() format"))
+            }
+            "switch_layout()" => Err(de::Error::custom(
+                "Invalid switch_layout() format, needs layout name inside",
+            )),
 
             // toggle() action
             a if VAR_TOGGLE_RE.is_match(a) => {
@@ -169,36 +447,61 @@ fn rate_youtube_video(fum: &mut Fum, rating: Rating) -> Option<reqwest::Response
     }
 }
 
+fn set_volume(player: &Player, volume: f64) -> Result<(), mpris::DBusError> {
+    player.set_volume(volume.clamp(0.0, 1.0))
+}
+
+/// Sets the playback rate, clamped to the range the player reports.
+fn set_rate(player: &Player, rate: f64) -> Result<(), mpris::DBusError> {
+    let min = player.get_minimum_playback_rate().ok();
+    let max = player.get_maximum_playback_rate().ok();
+
+    player.set_playback_rate(clamp_rate(rate, min, max))
+}
+
+/// Clamps the rate to the range the player reports. Rate can't be 0, that's pausing.
+fn clamp_rate(rate: f64, min: Option<f64>, max: Option<f64>) -> f64 {
+    let min = min.unwrap_or(0.0).max(MIN_RATE);
+    let max = max.unwrap_or(f64::MAX).max(min);
+
+    rate.clamp(min, max)
+}
+
+/// Rounds a stepped rate to hundredths, steps like 0.1 would drift away from round rates otherwise.
+fn round_rate(rate: f64) -> f64 {
+    (rate * 100.0).round() / 100.0
+}
+
 impl Action {
     pub fn run(action: &Action, fum: &mut Fum) -> FumResult<()> {
         match action {
             Action::Quit => fum.exit = true,
 
-            Action::Stop => if_player!(&fum.player, |player: &Player| player.stop()),
-            Action::Play => if_player!(&fum.player, |player: &Player| player.play()),
-            Action::Pause => if_player!(&fum.player, |player: &Player| player.pause()),
+            Action::Stop => if_player!(fum.players.active(), |player: &Player| player.stop()),
+            Action::Play => if_player!(fum.players.active(), |player: &Player| player.play()),
+            Action::Pause => if_player!(fum.players.active(), |player: &Player| player.pause()),
 
-            Action::Prev => if_player!(&fum.player, |player: &Player| player.previous()),
-            Action::PlayPause => if_player!(&fum.player, |player: &Player| player.play_pause()),
-            Action::Next => if_player!(&fum.player, |player: &Player| player.next()),
+            Action::Prev => if_player!(fum.players.active(), |player: &Player| player.previous()),
+            Action::PlayPause => if_player!(fum.players.active(), |player: &Player| player.play_pause()),
+            Action::Next => if_player!(fum.players.active(), |player: &Player| player.next()),
 
             Action::ShuffleOff => {
-                if_player!(&fum.player, |player: &Player| player.set_shuffle(true))
+                if_player!(fum.players.active(), |player: &Player| player.set_shuffle(false))
             }
-            Action::ShuffleToggle => if_player!(&fum.player, |player: &Player| player
+            Action::ShuffleToggle => if_player!(fum.players.active(), |player: &Player| player
                 .set_shuffle(!player.get_shuffle()?)),
             Action::ShuffleOn => {
-                if_player!(&fum.player, |player: &Player| player.set_shuffle(false))
+                if_player!(fum.players.active(), |player: &Player| player.set_shuffle(true))
             }
 
-            Action::LoopNone => if_player!(&fum.player, |player: &Player| player
+            Action::LoopNone => if_player!(fum.players.active(), |player: &Player| player
                 .set_loop_status(LoopStatus::None)),
-            Action::LoopPlaylist => if_player!(&fum.player, |player: &Player| player
+            Action::LoopPlaylist => if_player!(fum.players.active(), |player: &Player| player
                 .set_loop_status(LoopStatus::Playlist)),
-            Action::LoopTrack => if_player!(&fum.player, |player: &Player| player
+            Action::LoopTrack => if_player!(fum.players.active(), |player: &Player| player
                 .set_loop_status(LoopStatus::Track)),
             Action::LoopCycle => {
-                if let Some(player) = &fum.player {
+                if let Some(player) = fum.players.active() {
                     let loop_status = player.get_loop_status()?;
 
                     match loop_status {
@@ -209,7 +512,7 @@ impl Action {
                 }
             }
 
-            Action::Forward(offset) => if_player!(&fum.player, |player: &Player| {
+            Action::Forward(offset) => if_player!(fum.players.active(), |player: &Player| {
                 fum.redraw = true;
 
                 if let Some(track_id) = &fum.state.meta.track_id {
@@ -221,7 +524,7 @@ impl Action {
 
                 unreachable!()
             }),
-            Action::Backward(offset) => if_player!(&fum.player, |player: &Player| {
+            Action::Backward(offset) => if_player!(fum.players.active(), |player: &Player| {
                 fum.redraw = true;
 
                 if let Some(track_id) = &fum.state.meta.track_id {
@@ -236,6 +539,64 @@ impl Action {
                 unreachable!()
             }),
 
+            Action::VolumeUp(step) => try_player!(fum.players.active(), |player: &Player| {
+                set_volume(player, player.get_volume()? + *step as f64 / 100.0)
+            }),
+            Action::VolumeDown(step) => try_player!(fum.players.active(), |player: &Player| {
+                set_volume(player, player.get_volume()? - *step as f64 / 100.0)
+            }),
+            Action::VolumeSet(volume) => try_player!(fum.players.active(), |player: &Player| {
+                set_volume(player, *volume as f64 / 100.0)
+            }),
+            Action::MuteToggle => try_player!(fum.players.active(), |player: &Player| {
+                let volume = player.get_volume()?;
+
+                match fum.muted_volume {
+                    Some(muted_volume) if volume == 0.0 => set_volume(player, muted_volume)?,
+                    _ => set_volume(player, 0.0)?,
+                }
+
+                // Only remember the volume once the player actually muted
+                fum.muted_volume = if volume == 0.0 { None } else { Some(volume) };
+                Ok::<(), mpris::DBusError>(())
+            }),
+
+            Action::RateUp(step) => try_player!(fum.players.active(), |player: &Player| {
+                set_rate(player, round_rate(player.get_playback_rate()? + step))
+            }),
+            Action::RateDown(step) => try_player!(fum.players.active(), |player: &Player| {
+                set_rate(player, round_rate(player.get_playback_rate()? - step))
+            }),
+            Action::RateSet(rate) => {
+                try_player!(fum.players.active(), |player: &Player| set_rate(
+                    player, *rate
+                ))
+            }
+            Action::RateReset => {
+                try_player!(fum.players.active(), |player: &Player| set_rate(
+                    player, 1.0
+                ))
+            }
+
+            Action::NextPlayer => {
+                fum.players.next();
+                fum.player_changed();
+            }
+            Action::PrevPlayer => {
+                fum.players.prev();
+                fum.player_changed();
+            }
+            Action::SelectPlayer(name) => {
+                if fum.players.select(name) {
+                    fum.player_changed();
+                } else {
+                    fum.adopt_player(name);
+                }
+            }
+
+            Action::SwitchLayout(name) => fum.switch_layout(name),
+            Action::CycleLayout => fum.cycle_layout(),
+
             Action::Toggle(name, first, second) => {
                 fum.redraw = true;
 
@@ -251,7 +612,7 @@ impl Action {
                 fum.redraw = true;
 
                 // Just checks wether var exists, don't care about the value
-                if fum.state.vars.get(name).is_some() {
+                if fum.state.vars.contains_key(name) {
                     fum.state.vars.insert(name.to_string(), first.to_string());
                 }
             }
@@ -267,8 +628,42 @@ impl Action {
             Action::ClearVote => {
                 let _resp = rate_youtube_video(fum, Rating::None);
             }
+
+            Action::Exec(command) => {
+                let args = replace_args(command, &mut fum.state);
+                utils::etc::spawn(&args);
+            }
+            Action::Many(actions) => {
+                for action in actions {
+                    Action::run(action, fum)?;
+                }
+            }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_clamp_rate() {
+        assert_eq!(clamp_rate(0.125, None, None), 0.125);
+        assert_eq!(clamp_rate(0.0, None, None), MIN_RATE);
+        assert_eq!(clamp_rate(-1.0, Some(-2.0), None), MIN_RATE);
+        assert_eq!(clamp_rate(3.0, Some(0.5), Some(2.0)), 2.0);
+        assert_eq!(clamp_rate(0.25, Some(0.5), Some(2.0)), 0.5);
+        assert_eq!(clamp_rate(f64::INFINITY, None, None), f64::MAX);
+
+        // A maximum below the minimum leaves only the minimum
+        assert_eq!(clamp_rate(1.0, Some(1.5), Some(0.5)), 1.5);
+    }
+
+    #[test]
+    fn test_round_rate() {
+        assert_eq!(round_rate(1.0 + 0.1 + 0.1 + 0.1), 1.3);
+        assert_eq!(round_rate(0.125 + 0.25), 0.38);
+    }
+}
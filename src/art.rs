@@ -0,0 +1,272 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::Cursor,
+    path::PathBuf,
+    str::FromStr,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::SystemTime,
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use expanduser::expanduser;
+use image::{DynamicImage, ImageReader};
+use reqwest::{header::RANGE, Url};
+use sha1::{Digest, Sha1};
+
+use crate::fum::FumResult;
+
+/// On-disk cache of remote cover arts, keyed by `mpris:artUrl`.
+pub struct ArtCache {
+    dir: Option<PathBuf>,
+    limit: u64,
+}
+
+impl ArtCache {
+    /// Creates a cache under `$XDG_CACHE_HOME/fum` (or `~/.cache/fum`) holding at most `limit_mb` megabytes.
+    /// A limit of 0 disables the cache.
+    pub fn new(limit_mb: u64) -> Self {
+        let dir = match std::env::var("XDG_CACHE_HOME") {
+            Ok(cache_home) if !cache_home.is_empty() => Some(PathBuf::from(cache_home).join("fum")),
+            _ => expanduser("~/.cache/fum").ok(),
+        };
+
+        Self {
+            dir,
+            limit: limit_mb * 1024 * 1024,
+        }
+    }
+
+    /// Changes the size limit, evicting entries that no longer fit.
+    pub fn set_limit(&mut self, limit_mb: u64) {
+        self.limit = limit_mb * 1024 * 1024;
+
+        if self.limit > 0 {
+            self.evict();
+        }
+    }
+
+    /// Entries are named after the sha1 of the url, which stays the same across fum and Rust versions.
+    fn path(&self, url: &str) -> Option<PathBuf> {
+        if self.limit == 0 {
+            return None;
+        }
+
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{:x}", Sha1::digest(url.as_bytes()))))
+    }
+
+    fn get(&self, url: &str) -> Option<Vec<u8>> {
+        let path = self.path(url)?;
+        let bytes = fs::read(&path).ok()?;
+
+        // Bump the modification time so the least recently used arts are evicted first
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some(bytes)
+    }
+
+    fn put(&self, url: &str, bytes: &[u8]) {
+        let (Some(dir), Some(path)) = (&self.dir, self.path(url)) else {
+            return;
+        };
+
+        if fs::create_dir_all(dir).is_err() || fs::write(path, bytes).is_err() {
+            return;
+        }
+
+        self.evict();
+    }
+
+    /// Removes the oldest entries until the cache fits into its size limit.
+    fn evict(&self) {
+        let Some(entries) = self.dir.as_ref().and_then(|dir| fs::read_dir(dir).ok()) else {
+            return;
+        };
+
+        let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+
+                if !metadata.is_file() {
+                    return None;
+                }
+
+                Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+            })
+            .collect();
+
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+
+        // Oldest first
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        for (path, size, _) in files {
+            if total <= self.limit {
+                break;
+            }
+
+            if fs::remove_file(path).is_ok() {
+                total = total.saturating_sub(size);
+            }
+        }
+    }
+}
+
+/// Work for the art loader thread.
+enum Request {
+    Load(String),
+    /// New size limit of the cache, in megabytes.
+    CacheSize(u64),
+}
+
+/// Loads and decodes cover arts on a worker thread so slow art servers don't block the ui.
+pub struct ArtLoader {
+    requests: Sender<Request>,
+    results: Receiver<(String, Option<DynamicImage>)>,
+}
+
+impl ArtLoader {
+    pub fn new(mut cache: ArtCache) -> Self {
+        let (requests, request_receiver) = mpsc::channel::<Request>();
+        let (result_sender, results) = mpsc::channel();
+
+        thread::spawn(move || {
+            let client = reqwest::blocking::Client::new();
+
+            // Arts that failed to load are not tried again, they would be requested on every metadata refetch
+            let mut failed = HashSet::new();
+
+            for request in request_receiver {
+                let url = match request {
+                    Request::Load(url) => url,
+                    Request::CacheSize(limit_mb) => {
+                        cache.set_limit(limit_mb);
+                        continue;
+                    }
+                };
+
+                let image = match failed.contains(&url) {
+                    true => None,
+                    false => ArtLoader::load(&url, &client, &cache).ok(),
+                };
+
+                if image.is_none() {
+                    failed.insert(url.clone());
+                }
+
+                if result_sender.send((url, image)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { requests, results }
+    }
+
+    /// Queues the art at `url` to be loaded.
+    pub fn request(&self, url: &str) {
+        let _ = self.requests.send(Request::Load(url.to_string()));
+    }
+
+    /// Changes the size limit of the cache, e.g. after the config was reloaded.
+    pub fn set_cache_size(&self, limit_mb: u64) {
+        let _ = self.requests.send(Request::CacheSize(limit_mb));
+    }
+
+    /// Returns the loaded arts, `None` if the art failed to load.
+    pub fn try_iter(&self) -> impl Iterator<Item = (String, Option<DynamicImage>)> + '_ {
+        self.results.try_iter()
+    }
+
+    fn load(
+        art_url: &str,
+        client: &reqwest::blocking::Client,
+        cache: &ArtCache,
+    ) -> FumResult<DynamicImage> {
+        // Handle file:// scheme
+        if art_url.starts_with("file://") {
+            let art_path = Url::from_str(art_url)
+                .map_err(|err| format!("Failed to parse url: {art_url}: {err}"))?
+                .to_file_path()
+                .map_err(|_| format!("Failed to convert url: {art_url} to file_path"))?;
+
+            let bytes =
+                fs::read(&art_path).map_err(|err| format!("Failed to read art file: {err}"))?;
+
+            return ArtLoader::decode(bytes);
+        }
+
+        // Handle base64
+        if art_url.starts_with("data:") {
+            let base64_data = art_url
+                .split_once("base64,")
+                .ok_or("Invalid base64 url format")?
+                .1;
+
+            let bytes = BASE64_STANDARD
+                .decode(base64_data)
+                .map_err(|err| format!("Failed to decode base64 data: {err}"))?;
+
+            return ArtLoader::decode(bytes);
+        }
+
+        if let Some(bytes) = cache.get(art_url) {
+            if let Ok(image) = ArtLoader::decode(bytes) {
+                return Ok(image);
+            }
+        }
+
+        let resp = client
+            .get(art_url)
+            .header(RANGE, "bytes=0-1048576")
+            .send()
+            .map_err(|_| "Failed to fetch art url".to_string())?;
+
+        let bytes = resp
+            .bytes()
+            .map_err(|_| "Failed to get art image bytes".to_string())?;
+
+        let image = ArtLoader::decode(bytes.to_vec())?;
+
+        // Only cache arts that could be decoded
+        cache.put(art_url, &bytes);
+
+        Ok(image)
+    }
+
+    fn decode(bytes: Vec<u8>) -> FumResult<DynamicImage> {
+        let image = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|_| "Unknown image file_type".to_string())?
+            .decode()
+            .map_err(|_| "Failed to decode image".to_string())?;
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cache_path_is_stable() {
+        let cache = ArtCache {
+            dir: Some(PathBuf::from("/cache")),
+            limit: 1,
+        };
+
+        assert_eq!(
+            cache.path("https://example.com/art.png"),
+            Some(PathBuf::from(
+                "/cache/b356dea73efc09ae7b53633262df86b86ae22254"
+            ))
+        );
+    }
+}
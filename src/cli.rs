@@ -1,20 +1,21 @@
-use clap::Parser;
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::{Parser, Subcommand};
 use expanduser::expanduser;
 
 use crate::{
-    config::{Align, Config},
+    config::{self, Align, Config, IconPreset},
     fum::FumResult,
 };
 
 #[derive(Parser)]
 #[command(name = "fum", version, about)]
 struct FumCli {
-    #[arg(
-        short,
-        long,
-        value_name = "json file",
-        default_value = "~/.config/fum/config.json"
-    )]
+    /// Defaults to config.json, config.toml, config.yaml or config.yml in ~/.config/fum
+    #[arg(short, long, value_name = "json, toml or yaml file")]
     config: Option<String>,
 
     #[arg(short, long, value_name = "string[]", value_delimiter = ',')]
@@ -26,37 +27,151 @@ struct FumCli {
     #[arg(
         short,
         long,
-        value_name = "center,top,left,bottom,right,top-left,top-right,bottom-left,bottom-right"
+        value_name = "center,top,left,bottom,right,top-left,top-right,bottom-left,bottom-right,fill"
     )]
     align: Option<String>,
 
+    /// Name of the layout profile to start with
+    #[arg(short, long, value_name = "string")]
+    layout: Option<String>,
+
+    /// Icon set to use, glyphs set in the config's icons section still apply
+    #[arg(long, value_name = "nerd,unicode,ascii")]
+    icons: Option<String>,
+
     #[arg(long)]
     authorize: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-pub fn run() -> FumResult<Config> {
-    let fum_cli = FumCli::parse();
+#[derive(Subcommand)]
+enum Command {
+    /// Validate a config file and report every problem found in it
+    CheckConfig {
+        /// Defaults to the --config path
+        #[arg(value_name = "json, toml or yaml file")]
+        path: Option<String>,
+    },
+}
 
-    let config_path = expanduser(&fum_cli.config.unwrap())
-        .map_err(|err| format!("Failed to expand path: {err}"))?;
+/// Options given on the command line, reapplied on top of the config whenever it's reloaded.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    players: Option<Vec<String>>,
+    use_active_player: Option<bool>,
+    align: Option<Align>,
+    icons: Option<IconPreset>,
 
-    let mut config = Config::load(&config_path)?;
+    /// Not applied to the config, only picks the layout profile to start with.
+    pub layout: Option<String>,
+}
+
+impl Overrides {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(players) = self.players.as_ref() {
+            config.players = players.to_owned();
+        }
+
+        if let Some(use_active_player) = self.use_active_player.as_ref() {
+            config.use_active_player = use_active_player.to_owned();
+        }
+
+        if let Some(align) = self.align.as_ref() {
+            config.align = align.to_owned();
+        }
 
-    if let Some(players) = fum_cli.players.as_ref() {
-        config.players = players.to_owned();
+        if let Some(icons) = self.icons.as_ref() {
+            config.icons.preset = icons.to_owned();
+        }
     }
+}
+
+/// The first config found in ~/.config/fum, config.json if there is none yet.
+fn default_config_path() -> FumResult<PathBuf> {
+    let dir = expanduser("~/.config/fum").map_err(|err| format!("Failed to expand path: {err}"))?;
+
+    let path = ["config.json", "config.toml", "config.yaml", "config.yml"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join("config.json"));
+
+    Ok(path)
+}
 
-    if let Some(use_active_player) = fum_cli.use_active_player.as_ref() {
-        config.use_active_player = use_active_player.to_owned();
+/// Prints the problems of the config, returns the exit code.
+fn check_config(path: &Path) -> i32 {
+    match config::check(path) {
+        Ok(issues) if issues.is_empty() => {
+            println!("{}: ok", path.display());
+            0
+        }
+        Ok(issues) => {
+            for issue in issues.iter() {
+                eprintln!("{}: {}: {}", path.display(), issue.location, issue.message);
+            }
+
+            1
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
     }
+}
+
+pub fn run() -> FumResult<Config> {
+    let fum_cli = FumCli::parse();
+
+    let config_path = match fum_cli.config {
+        Some(config) => {
+            expanduser(config).map_err(|err| format!("Failed to expand path: {err}"))?
+        }
+        None => default_config_path()?,
+    };
 
-    if let Some(align) = fum_cli.align.as_ref() {
-        let align =
-            Align::from_str(align.as_str()).ok_or("Invalid value for 'align'".to_string())?;
+    if let Some(Command::CheckConfig { path }) = fum_cli.command {
+        let path = match path {
+            Some(path) => {
+                expanduser(path).map_err(|err| format!("Failed to expand path: {err}"))?
+            }
+            None => config_path,
+        };
 
-        config.align = align;
+        process::exit(check_config(&path));
     }
 
+    let align = match fum_cli.align.as_ref() {
+        Some(align) => {
+            Some(Align::from_str(align.as_str()).ok_or("Invalid value for 'align'".to_string())?)
+        }
+        None => None,
+    };
+
+    let icons = match fum_cli.icons.as_ref() {
+        Some(icons) => Some(
+            IconPreset::from_str(icons.as_str()).ok_or("Invalid value for 'icons'".to_string())?,
+        ),
+        None => None,
+    };
+
+    let overrides = Overrides {
+        players: fum_cli.players,
+        use_active_player: fum_cli.use_active_player,
+        align,
+        icons,
+        layout: fum_cli.layout,
+    };
+
+    let mut config = Config::load(&config_path)?;
+
+    overrides.apply(&mut config);
+
+    config.path = config_path;
+    config.overrides = overrides;
+
     if fum_cli.authorize {
         config.authorize = true;
     }
@@ -0,0 +1,678 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+};
+
+use ratatui::layout::Constraint;
+use serde::{
+    de::{self, IgnoredAny, IntoDeserializer, MapAccess, Visitor},
+    Deserialize,
+};
+use serde_json::Value;
+
+use crate::{
+    fum::FumResult,
+    meta::Meta,
+    regexes::{GET_META_RE, VAR_RE},
+    state::FumState,
+    template,
+    text::VARIABLES,
+    widget::{Direction, FumWidget, Size},
+};
+
+use super::{
+    compose, format::ParseError, keybind::Keybind, Align, Config, Profile, DEFAULT_PROFILE,
+};
+
+/// Fields every widget accepting mouse bindings has.
+const MOUSE_FIELDS: &[&str] = &[
+    "on_right_click",
+    "on_middle_click",
+    "on_scroll_up",
+    "on_scroll_down",
+];
+
+/// Text modifiers every widget has.
+const STYLE_FIELDS: &[&str] = &["bold", "italic", "underline", "dim", "reversed"];
+
+/// A problem found in a config that parses fine.
+#[derive(Debug)]
+pub struct Issue {
+    pub location: String,
+    pub message: String,
+}
+
+/// Keybinds in the order they're written, duplicates included.
+#[derive(Default)]
+struct RawKeybinds(Vec<String>);
+
+impl<'de> Deserialize<'de> for RawKeybinds {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct RawKeybindsVisitor;
+
+        impl<'de> Visitor<'de> for RawKeybindsVisitor {
+            type Value = RawKeybinds;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of keybinds")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut keybinds = Vec::new();
+
+                while let Some(keybind) = map.next_key::<String>()? {
+                    map.next_value::<IgnoredAny>()?;
+                    keybinds.push(keybind);
+                }
+
+                Ok(RawKeybinds(keybinds))
+            }
+        }
+
+        deserializer.deserialize_map(RawKeybindsVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+struct RawConfig {
+    #[serde(default)]
+    keybinds: RawKeybinds,
+}
+
+/// Validates the config file, errors if it can't be loaded at all.
+pub fn check(path: &Path) -> FumResult<Vec<Issue>> {
+    let file = fs::read_to_string(path)
+        .map_err(|err| format!("{}: Failed to read config: {err}", path.display()))?;
+
+    check_file(path, &file)
+}
+
+/// Validates the contents of the config file at `path`.
+fn check_file(path: &Path, file: &str) -> FumResult<Vec<Issue>> {
+    let at = |err: ParseError| {
+        let path = err.path.as_deref().unwrap_or(path).display();
+
+        match err.location {
+            Some((line, column)) => format!("{path}:{line}:{column}: {}", err.message),
+            None => format!("{path}: {}", err.message),
+        }
+    };
+
+    let config: Config = compose::parse(path, file).map_err(at)?;
+    let value: Value = compose::parse(path, file).map_err(at)?;
+    let raw: RawConfig = compose::parse(path, file).map_err(at)?;
+
+    let mut issues = Vec::new();
+
+    let var_names = config.var_names();
+
+    for name in config.profile_names() {
+        let (location, widgets) = match name {
+            DEFAULT_PROFILE => ("layout".to_string(), value.get("layout")),
+            name => (
+                format!("layouts.{name}.layout"),
+                value
+                    .get("layouts")
+                    .and_then(|layouts| layouts.get(name))
+                    .and_then(|profile| profile.get("layout")),
+            ),
+        };
+
+        if let Some(Value::Array(widgets)) = widgets {
+            for (i, widget) in widgets.iter().enumerate() {
+                check_widget(widget, &format!("{location}[{i}]"), &var_names, &mut issues);
+            }
+        }
+
+        // The default layout isn't the user's to fix
+        if name == DEFAULT_PROFILE && widgets.is_none() {
+            continue;
+        }
+
+        if let Some(profile) = config.profile(name) {
+            check_ids(profile.layout, &location, &mut issues);
+            check_size(&profile, &location, &mut issues);
+        }
+    }
+
+    for (i, breakpoint) in config.breakpoints.iter().enumerate() {
+        if config.profile(&breakpoint.layout).is_none() {
+            issues.push(Issue {
+                location: format!("breakpoints[{i}]"),
+                message: format!("Unknown layout `{}`", breakpoint.layout),
+            });
+        }
+    }
+
+    check_keybinds(&raw.keybinds, &mut issues);
+
+    Ok(issues)
+}
+
+fn widget_fields(widget_type: &str) -> Option<(&'static [&'static str], bool)> {
+    match widget_type {
+        "container" => Some((
+            &[
+                "width",
+                "height",
+                "direction",
+                "children",
+                "flex",
+                "bg",
+                "fg",
+                "border",
+                "border_fg",
+                "title",
+                "title_align",
+                "padding",
+                "margin",
+            ],
+            true,
+        )),
+        "cover-art" => Some((&["width", "height", "resize", "bg", "fg"], true)),
+        "label" => Some((
+            &[
+                "id",
+                "text",
+                "size",
+                "align",
+                "truncate",
+                "truncate_position",
+                "ellipsis",
+                "scroll",
+                "scroll_speed",
+                "scroll_pause",
+                "scroll_separator",
+                "bg",
+                "fg",
+            ],
+            true,
+        )),
+        "button" => Some((&["id", "text", "size", "action", "exec", "bg", "fg"], true)),
+        "progress" => Some((&["id", "size", "progress", "empty"], true)),
+        "empty" => Some((&["size", "bg", "fg"], false)),
+        "volume" => Some((&["id", "size", "step", "progress", "empty"], true)),
+        "player-list" => Some((
+            &[
+                "id",
+                "width",
+                "height",
+                "bg",
+                "fg",
+                "active_bg",
+                "active_fg",
+            ],
+            false,
+        )),
+        _ => None,
+    }
+}
+
+fn check_widget(
+    widget: &Value,
+    location: &str,
+    var_names: &HashSet<String>,
+    issues: &mut Vec<Issue>,
+) {
+    let Value::Object(fields) = widget else {
+        return;
+    };
+
+    let widget_type = fields
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    if let Some((known, mouse)) = widget_fields(widget_type) {
+        for field in fields.keys() {
+            let is_known = field == "type"
+                || known.contains(&field.as_str())
+                || STYLE_FIELDS.contains(&field.as_str())
+                || (mouse && MOUSE_FIELDS.contains(&field.as_str()));
+
+            if !is_known {
+                issues.push(Issue {
+                    location: location.to_string(),
+                    message: format!("Unknown field `{field}` for {widget_type}"),
+                });
+            }
+        }
+    }
+
+    if let Some(Value::String(text)) = fields.get("text") {
+        for error in template::errors(text) {
+            issues.push(Issue {
+                location: location.to_string(),
+                message: format!("Invalid expression {error}"),
+            });
+        }
+
+        for variable in unknown_variables(text, var_names) {
+            issues.push(Issue {
+                location: location.to_string(),
+                message: format!("Unknown variable `{variable}`"),
+            });
+        }
+    }
+
+    if let Some(Value::Array(children)) = fields.get("children") {
+        for (i, child) in children.iter().enumerate() {
+            check_widget(
+                child,
+                &format!("{location}.children[{i}]"),
+                var_names,
+                issues,
+            );
+        }
+    }
+}
+
+/// Variables in the text that would be shown as is.
+fn unknown_variables(text: &str, var_names: &HashSet<String>) -> Vec<String> {
+    // Whatever is inside var() and get_meta() is up to the user
+    let text = VAR_RE.replace_all(text, "");
+    let text = GET_META_RE.replace_all(&text, "");
+
+    let mut unknown = Vec::new();
+
+    // Expressions may name variables without their `$`, the ones with it are found below
+    for name in template::variables(&text) {
+        let variable = format!("${name}");
+
+        if !name.starts_with('$')
+            && !VARIABLES.contains(&variable.as_str())
+            && !var_names.contains(&variable)
+        {
+            unknown.push(name);
+        }
+    }
+
+    for (i, _) in text.match_indices('$') {
+        let rest = &text[i..];
+
+        // Same as replace_text, a variable may be directly followed by anything
+        let known = VARIABLES.iter().any(|variable| rest.starts_with(variable))
+            || var_names
+                .iter()
+                .any(|variable| rest.starts_with(variable.as_str()));
+
+        let name: String = rest
+            .chars()
+            .enumerate()
+            .take_while(|(i, c)| *i == 0 || c.is_alphanumeric() || *c == '_' || *c == '-')
+            .map(|(_, c)| c)
+            .collect();
+
+        let is_variable = name.chars().nth(1).is_some_and(|c| c.is_alphabetic());
+
+        if !known && is_variable {
+            unknown.push(name);
+        }
+    }
+
+    unknown
+}
+
+fn check_keybinds(keybinds: &RawKeybinds, issues: &mut Vec<Issue>) {
+    let mut seen: HashMap<Vec<Keybind>, &str> = HashMap::new();
+
+    for raw in keybinds.0.iter() {
+        let deserializer: de::value::StrDeserializer<de::value::Error> =
+            raw.as_str().into_deserializer();
+
+        // Invalid keybinds already failed the parsing
+        let Ok(keybind) = Keybind::deserialize(deserializer) else {
+            continue;
+        };
+
+        for sequence in keybind.sequences() {
+            match seen.get(sequence) {
+                Some(first) => issues.push(Issue {
+                    location: format!("keybinds.\"{raw}\""),
+                    message: format!("Keys are already bound by \"{first}\""),
+                }),
+                None => {
+                    seen.insert(sequence.to_vec(), raw);
+                }
+            }
+        }
+    }
+}
+
+fn check_ids(layout: &[FumWidget], location: &str, issues: &mut Vec<Issue>) {
+    fn collect<'a>(widget: &'a FumWidget, ids: &mut Vec<&'a str>) {
+        match widget {
+            FumWidget::Container { children, .. } => {
+                children.iter().for_each(|child| collect(child, ids))
+            }
+            FumWidget::Label { id, .. }
+            | FumWidget::Button { id, .. }
+            | FumWidget::Progress { id, .. }
+            | FumWidget::Volume { id, .. }
+            | FumWidget::PlayerList { id, .. } => ids.push(id),
+            _ => {}
+        }
+    }
+
+    let mut ids = Vec::new();
+    layout.iter().for_each(|widget| collect(widget, &mut ids));
+
+    let mut seen = HashSet::new();
+
+    for id in ids {
+        if !seen.insert(id) {
+            issues.push(Issue {
+                location: location.to_string(),
+                message: format!("Widget id `{id}` is used more than once"),
+            });
+        }
+    }
+}
+
+fn check_size(profile: &Profile, location: &str, issues: &mut Vec<Issue>) {
+    let mut state = FumState::new(Meta::default());
+
+    // A filling layout is as big as the terminal
+    let size = match profile.align {
+        Align::Fill => (None, None),
+        _ => (Some(profile.width), Some(profile.height)),
+    };
+
+    check_children(
+        profile.layout,
+        profile.direction,
+        size,
+        location,
+        &mut state,
+        issues,
+    );
+}
+
+/// Checks that the fixed sizes of the children fit into the parent.
+fn check_children(
+    children: &[FumWidget],
+    direction: &Direction,
+    (width, height): (Option<u16>, Option<u16>),
+    location: &str,
+    state: &mut FumState,
+    issues: &mut Vec<Issue>,
+) {
+    state.parent_direction = direction.to_owned();
+
+    let needed: u32 = children
+        .iter()
+        .map(|child| match child.get_size(state) {
+            Constraint::Length(size) | Constraint::Min(size) => size as u32,
+            _ => 0,
+        })
+        .sum();
+
+    let (available, cross, dimension) = match direction {
+        Direction::Horizontal => (width, height, "width"),
+        Direction::Vertical => (height, width, "height"),
+    };
+
+    if let Some(available) = available {
+        if needed > available as u32 {
+            issues.push(Issue {
+                location: location.to_string(),
+                message: format!(
+                    "Children need a {dimension} of {needed} but only {available} is available"
+                ),
+            });
+        }
+    }
+
+    for (i, child) in children.iter().enumerate() {
+        let location = format!("{location}[{i}]");
+
+        let (child_width, child_height) = match child {
+            FumWidget::Container { width, height, .. }
+            | FumWidget::CoverArt { width, height, .. }
+            | FumWidget::PlayerList { width, height, .. } => {
+                (width.and_then(Size::length), height.and_then(Size::length))
+            }
+            _ => (None, None),
+        };
+
+        let child_cross = match direction {
+            Direction::Horizontal => child_height,
+            Direction::Vertical => child_width,
+        };
+
+        if let (Some(child_cross), Some(cross)) = (child_cross, cross) {
+            if child_cross > cross {
+                let dimension = match direction {
+                    Direction::Horizontal => "height",
+                    Direction::Vertical => "width",
+                };
+
+                issues.push(Issue {
+                    location: location.clone(),
+                    message: format!(
+                        "{dimension} of {child_cross} doesn't fit into the parent's {cross}"
+                    ),
+                });
+            }
+        }
+
+        if let FumWidget::Container {
+            width,
+            height,
+            direction: child_direction,
+            children,
+            border,
+            title,
+            padding,
+            margin,
+            ..
+        } = child
+        {
+            // An unset size takes up the parent's one, a relative one a part of it
+            let cross_size = |size: &Option<Size>| match (size, cross) {
+                (Some(size), Some(cross)) => Some(size.resolve(cross)),
+                (Some(size), None) => size.length(),
+                (None, cross) => cross,
+            };
+
+            // The main axis is split by this layout, only relative sizes can be told up front
+            let main_size = |size: &Option<Size>| match (size, available) {
+                (Some(size @ (Size::Percentage(_) | Size::Ratio(..))), Some(available)) => {
+                    Some(size.resolve(available))
+                }
+                (size, _) => size.and_then(Size::length),
+            };
+
+            let (width, height) = match direction {
+                Direction::Horizontal => (main_size(width), cross_size(height)),
+                Direction::Vertical => (cross_size(width), main_size(height)),
+            };
+
+            // Children only get what's inside of the margin, border and padding
+            let border = match border.to_border_type() {
+                Some(_) => 2,
+                None => 0,
+            };
+
+            // Without a border, the title takes a row of its own
+            let title = match (border, title) {
+                (0, Some(_)) => 1,
+                _ => 0,
+            };
+
+            let size = (
+                width.map(|width| {
+                    width.saturating_sub(margin.horizontal() + padding.horizontal() + border)
+                }),
+                height.map(|height| {
+                    height.saturating_sub(margin.vertical() + padding.vertical() + border + title)
+                }),
+            );
+
+            check_children(
+                children,
+                child_direction,
+                size,
+                &format!("{location}.children"),
+                state,
+                issues,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{action::MouseBindings, widget::TextStyle};
+
+    /// Names of the top level fields in the debug output of `value`.
+    fn debug_fields(value: &impl fmt::Debug) -> Vec<String> {
+        format!("{value:#?}")
+            .lines()
+            .filter_map(|line| line.strip_prefix("    "))
+            .filter(|line| !line.starts_with(' '))
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    fn messages(config: &str) -> Vec<String> {
+        check_file(Path::new("config.json"), config)
+            .unwrap()
+            .into_iter()
+            .map(|issue| format!("{}: {}", issue.location, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_widget_fields_match_the_widgets() {
+        let widgets = [
+            json!({ "type": "container", "children": [] }),
+            json!({ "type": "cover-art" }),
+            json!({ "type": "label", "text": "" }),
+            json!({ "type": "button", "text": "" }),
+            json!({ "type": "progress", "progress": {}, "empty": {} }),
+            json!({ "type": "empty", "size": 1 }),
+            json!({ "type": "volume", "progress": {}, "empty": {} }),
+            json!({ "type": "player-list" }),
+        ];
+
+        for widget in widgets {
+            let widget: FumWidget = serde_json::from_value(widget).unwrap();
+
+            // A new widget has to be added above
+            let widget_type = match widget {
+                FumWidget::Container { .. } => "container",
+                FumWidget::CoverArt { .. } => "cover-art",
+                FumWidget::Label { .. } => "label",
+                FumWidget::Button { .. } => "button",
+                FumWidget::Progress { .. } => "progress",
+                FumWidget::Empty { .. } => "empty",
+                FumWidget::Volume { .. } => "volume",
+                FumWidget::PlayerList { .. } => "player-list",
+            };
+
+            let (known, mouse) = widget_fields(widget_type).unwrap();
+            let fields = debug_fields(&widget);
+
+            for field in fields.iter() {
+                match field.as_str() {
+                    "style" => {}
+                    "mouse" => assert!(mouse, "{widget_type} has mouse bindings"),
+                    field => assert!(
+                        known.contains(&field),
+                        "`{field}` of {widget_type} is unknown"
+                    ),
+                }
+            }
+
+            assert_eq!(
+                known.len(),
+                fields
+                    .iter()
+                    .filter(|field| *field != "style" && *field != "mouse")
+                    .count()
+            );
+            assert_eq!(mouse, fields.iter().any(|field| field == "mouse"));
+        }
+
+        assert_eq!(debug_fields(&TextStyle::default()), STYLE_FIELDS);
+        assert_eq!(debug_fields(&MouseBindings::default()), MOUSE_FIELDS);
+    }
+
+    #[test]
+    fn test_check_finds_issues() {
+        let config = r#"{
+            "width": 10,
+            "height": 2,
+            "keybinds": { "q": "quit()", "ctrl+c;q": "quit()" },
+            "breakpoints": [{ "min_width": 80, "layout": "wide" }],
+            "layout": [
+                { "type": "label", "text": "$titel {album | upper}", "colour": "red" },
+                { "type": "button", "id": "play", "text": "$status_icon", "size": 8 },
+                { "type": "button", "id": "play", "text": "$status_icon", "size": 8 }
+            ]
+        }"#;
+
+        assert_eq!(
+            messages(config),
+            vec![
+                "layout[0]: Unknown field `colour` for label",
+                "layout[0]: Unknown variable `$titel`",
+                "layout: Widget id `play` is used more than once",
+                "layout: Children need a height of 17 but only 2 is available",
+                "breakpoints[0]: Unknown layout `wide`",
+                "keybinds.\"ctrl+c;q\": Keys are already bound by \"q\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_sizes_columns_in_rows() {
+        let config = r#"{
+            "width": 10,
+            "height": 4,
+            "direction": "horizontal",
+            "layout": [
+                { "type": "container", "direction": "vertical", "width": 9, "children": [] },
+                {
+                    "type": "container",
+                    "direction": "vertical",
+                    "width": "50%",
+                    "children": [{ "type": "container", "width": 6, "children": [] }]
+                },
+                { "type": "container", "direction": "vertical", "width": "min:2", "children": [] }
+            ]
+        }"#;
+
+        assert_eq!(
+            messages(config),
+            vec![
+                "layout: Children need a width of 11 but only 10 is available",
+                "layout[1].children[0]: width of 6 doesn't fit into the parent's 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_accepts_the_default_config() {
+        assert!(messages("{}").is_empty());
+    }
+
+    #[test]
+    fn test_check_reports_where_parsing_failed() {
+        let err = check_file(Path::new("config.json"), "{\n  \"width\": \"wide\"\n}").unwrap_err();
+
+        assert!(err.to_string().starts_with("config.json:2:"), "{err}");
+    }
+}
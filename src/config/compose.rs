@@ -0,0 +1,473 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use expanduser::expanduser;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use super::format::{Format, ParseError};
+
+/// How deep templates can be nested in each other, mostly to stop templates that use themselves.
+const MAX_TEMPLATE_DEPTH: usize = 16;
+
+/// Parses a config, merging in the files it includes and expanding its templates.
+pub fn parse<T: DeserializeOwned>(path: &Path, file: &str) -> Result<T, ParseError> {
+    parse_with_includes(path, file).map(|(config, _)| config)
+}
+
+/// Like `parse`, also returns the paths of every file the config includes, nested ones too.
+pub fn parse_with_includes<T: DeserializeOwned>(
+    path: &Path,
+    file: &str,
+) -> Result<(T, Vec<PathBuf>), ParseError> {
+    let format = Format::from_path(path);
+    let value: Value = format.parse(file)?;
+
+    // Parse the file directly when possible, so that errors keep their location
+    if value.get("include").is_none() && value.get("templates").is_none() {
+        return Ok((format.parse(file)?, Vec::new()));
+    }
+
+    let error = |message: String| ParseError {
+        path: None,
+        location: None,
+        message,
+    };
+
+    let mut sources = vec![(path.to_path_buf(), file.to_string())];
+    let mut value = resolve_includes(value, path, &mut Vec::new(), &mut sources)?;
+
+    if let Value::Object(config) = &mut value {
+        let templates = match config.remove("templates") {
+            Some(Value::Object(templates)) => templates,
+            Some(_) => return Err(error("'templates' must be a map of widgets".to_string())),
+            None => Map::new(),
+        };
+
+        if let Some(Value::Array(layout)) = config.get_mut("layout") {
+            expand_all(layout, &templates, 0).map_err(error)?;
+        }
+
+        if let Some(Value::Object(profiles)) = config.get_mut("layouts") {
+            for profile in profiles.values_mut() {
+                if let Some(Value::Array(layout)) = profile.get_mut("layout") {
+                    expand_all(layout, &templates, 0).map_err(error)?;
+                }
+            }
+        }
+    }
+
+    let config =
+        serde_json::from_value(value).map_err(|err| locate::<T>(err.to_string(), &sources))?;
+    let includes = sources.into_iter().skip(1).map(|(path, _)| path).collect();
+
+    Ok((config, includes))
+}
+
+/// Finds the file an error of the composed config comes from by parsing each file on its own, which
+/// also gives the error its location. Errors that only show up once composed, e.g. in a template, have neither.
+fn locate<T: DeserializeOwned>(message: String, sources: &[(PathBuf, String)]) -> ParseError {
+    // The config itself first, then the includes from the last one as later ones take precedence
+    let order = std::iter::once(0).chain((1..sources.len()).rev());
+
+    for i in order {
+        let (path, file) = &sources[i];
+
+        if let Err(err) = Format::from_path(path).parse::<T>(file) {
+            if err.message == message {
+                return ParseError {
+                    path: (i > 0).then(|| path.to_path_buf()),
+                    ..err
+                };
+            }
+        }
+    }
+
+    ParseError {
+        path: None,
+        location: None,
+        message: format!("{message} (after applying includes and templates)"),
+    }
+}
+
+/// Merges the included files under the config, the including one takes precedence.
+/// Every included file is added to `sources` to locate errors later on.
+fn resolve_includes(
+    mut value: Value,
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    sources: &mut Vec<(PathBuf, String)>,
+) -> Result<Value, ParseError> {
+    // Problems with the includes of an included file are reported in that file
+    let nested = !stack.is_empty();
+    let error = |message: String| ParseError {
+        path: nested.then(|| path.to_path_buf()),
+        location: None,
+        message,
+    };
+
+    let Value::Object(config) = &mut value else {
+        return Ok(value);
+    };
+
+    let includes = match config.remove("include") {
+        None => Vec::new(),
+        Some(Value::String(include)) => vec![include],
+        Some(Value::Array(includes)) => includes
+            .into_iter()
+            .map(|include| match include {
+                Value::String(include) => Ok(include),
+                _ => Err(error(
+                    "'include' must be a path or a list of paths".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<String>, ParseError>>()?,
+        Some(_) => {
+            return Err(error(
+                "'include' must be a path or a list of paths".to_string(),
+            ))
+        }
+    };
+
+    if includes.is_empty() {
+        return Ok(value);
+    }
+
+    stack.push(path.canonicalize().unwrap_or(path.to_path_buf()));
+
+    let mut merged = Value::Object(Map::new());
+
+    for include in includes {
+        let include_path = include_path(path, &include).map_err(error)?;
+        let canonical = include_path
+            .canonicalize()
+            .unwrap_or(include_path.to_path_buf());
+
+        if stack.contains(&canonical) {
+            let message = format!("{} is included recursively", include_path.display());
+            return Err(error(message));
+        }
+
+        let file = fs::read_to_string(&include_path)
+            .map_err(|err| error(format!("Failed to read {}: {err}", include_path.display())))?;
+
+        let included: Value = Format::from_path(&include_path)
+            .parse(&file)
+            .map_err(|err| ParseError {
+                path: Some(include_path.clone()),
+                ..err
+            })?;
+
+        sources.push((include_path.clone(), file));
+
+        let included = resolve_includes(included, &include_path, stack, sources)?;
+
+        merge(&mut merged, included);
+    }
+
+    stack.pop();
+
+    merge(&mut merged, value);
+
+    Ok(merged)
+}
+
+/// Included paths are relative to the file including them.
+fn include_path(path: &Path, include: &str) -> Result<PathBuf, String> {
+    let include = expanduser(include).map_err(|err| format!("Failed to expand path: {err}"))?;
+
+    match path.parent() {
+        Some(dir) if include.is_relative() => Ok(dir.join(include)),
+        _ => Ok(include),
+    }
+}
+
+/// Merges maps key by key, anything else is replaced.
+fn merge(base: &mut Value, value: Value) {
+    match (base, value) {
+        (Value::Object(base), Value::Object(value)) => {
+            for (key, value) in value {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+/// Replaces every template widget in the list and in its children.
+fn expand_all(
+    widgets: &mut Vec<Value>,
+    templates: &Map<String, Value>,
+    depth: usize,
+) -> Result<(), String> {
+    let mut expanded = Vec::with_capacity(widgets.len());
+
+    for widget in widgets.drain(..) {
+        match expand(widget, templates, depth)? {
+            Value::Array(widgets) => expanded.extend(widgets),
+            widget => expanded.push(widget),
+        }
+    }
+
+    *widgets = expanded;
+
+    Ok(())
+}
+
+/// A template can stand for a single widget or for a list of them.
+fn expand(widget: Value, templates: &Map<String, Value>, depth: usize) -> Result<Value, String> {
+    let mut widget = match widget.get("type").and_then(Value::as_str) {
+        Some("template") => {
+            if depth >= MAX_TEMPLATE_DEPTH {
+                return Err("Templates are nested too deep".to_string());
+            }
+
+            let name = widget
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or("Template widget needs a 'name'")?;
+
+            let template = templates
+                .get(name)
+                .ok_or(format!("Unknown template: {name}"))?;
+
+            let params = match widget.get("params") {
+                Some(Value::Object(params)) => params.clone(),
+                Some(_) => return Err(format!("'params' of template {name} must be a map")),
+                None => Map::new(),
+            };
+
+            let instance = substitute(template.clone(), &params, name)?;
+
+            return match instance {
+                Value::Array(mut widgets) => {
+                    expand_all(&mut widgets, templates, depth + 1)?;
+                    Ok(Value::Array(widgets))
+                }
+                widget => expand(widget, templates, depth + 1),
+            };
+        }
+        _ => widget,
+    };
+
+    if let Some(Value::Array(children)) = widget.get_mut("children") {
+        expand_all(children, templates, depth)?;
+    }
+
+    Ok(widget)
+}
+
+/// Replaces `{{param}}` in every string, a string that is only a placeholder takes the param's value as is.
+/// Braces escaped with a backslash belong to text expressions and are left alone.
+fn substitute(value: Value, params: &Map<String, Value>, name: &str) -> Result<Value, String> {
+    match value {
+        Value::String(string) => {
+            let placeholder = string
+                .strip_prefix("{{")
+                .and_then(|string| string.strip_suffix("}}"))
+                .filter(|param| !param.contains("{{") && !param.contains("}}"));
+
+            if let Some(param) = placeholder {
+                return params.get(param.trim()).cloned().ok_or(format!(
+                    "Template {name} is missing the '{}' param",
+                    param.trim()
+                ));
+            }
+
+            let mut result = String::new();
+            let mut rest = string.as_str();
+
+            while let Some(start) = rest.find("{{") {
+                // An escaped brace of a text expression, e.g. `\{{{param}}\}`
+                if rest[..start].ends_with('\\') {
+                    result.push_str(&rest[..=start]);
+                    rest = &rest[start + 1..];
+                    continue;
+                }
+
+                let Some(end) = rest[start..].find("}}") else {
+                    break;
+                };
+
+                let param = rest[start + 2..start + end].trim();
+                let value = params
+                    .get(param)
+                    .ok_or(format!("Template {name} is missing the '{param}' param"))?;
+
+                result.push_str(&rest[..start]);
+
+                match value {
+                    Value::String(value) => result.push_str(value),
+                    value => result.push_str(&value.to_string()),
+                }
+
+                rest = &rest[start + end + 2..];
+            }
+
+            result.push_str(rest);
+
+            Ok(Value::String(result))
+        }
+        Value::Array(values) => Ok(Value::Array(
+            values
+                .into_iter()
+                .map(|value| substitute(value, params, name))
+                .collect::<Result<Vec<Value>, String>>()?,
+        )),
+        Value::Object(map) => Ok(Value::Object(
+            map.into_iter()
+                .map(|(key, value)| Ok((key, substitute(value, params, name)?)))
+                .collect::<Result<Map<String, Value>, String>>()?,
+        )),
+        value => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::Config, meta::Meta, state::FumState, text::replace_text};
+
+    #[test]
+    fn test_template_params_and_literal_braces() {
+        let config = r#"{
+            "templates": {
+                "note": { "type": "label", "text": "\\{{{kind}}\\} {title} \\{title\\}" }
+            },
+            "layout": [{ "type": "template", "name": "note", "params": { "kind": "now" } }]
+        }"#;
+
+        let value: Value = parse(Path::new("config.json"), config).unwrap();
+        let text = value["layout"][0]["text"].as_str().unwrap();
+
+        assert_eq!(text, "\\{now\\} {title} \\{title\\}");
+
+        let mut state = FumState::new(Meta::default());
+
+        assert_eq!(replace_text(text, &mut state), "{now} No Music {title}");
+    }
+
+    /// A directory of its own for the files of a test.
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fum-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_substitute() {
+        let params = serde_json::json!({ "title": "Now", "size": 3 });
+        let Value::Object(params) = params else {
+            unreachable!()
+        };
+
+        let template = serde_json::json!({
+            "text": "{{title}}: {{ size }} {title}",
+            "size": "{{size}}",
+            "children": ["{{title}}"]
+        });
+
+        assert_eq!(
+            substitute(template, &params, "note").unwrap(),
+            serde_json::json!({ "text": "Now: 3 {title}", "size": 3, "children": ["Now"] })
+        );
+
+        assert_eq!(
+            substitute(Value::from("{{kind}}"), &params, "note").unwrap_err(),
+            "Template note is missing the 'kind' param"
+        );
+    }
+
+    #[test]
+    fn test_templates_nested_too_deep() {
+        let config = r#"{
+            "templates": { "loop": { "type": "template", "name": "loop" } },
+            "layout": [{ "type": "template", "name": "loop" }]
+        }"#;
+
+        let err = parse::<Value>(Path::new("config.json"), config).unwrap_err();
+
+        assert_eq!(err.message, "Templates are nested too deep");
+    }
+
+    #[test]
+    fn test_recursive_include() {
+        let dir = dir("recursive-include");
+
+        fs::write(dir.join("a.json"), r#"{ "include": "b.json" }"#).unwrap();
+        fs::write(dir.join("b.json"), r#"{ "include": "a.json" }"#).unwrap();
+
+        let config = r#"{ "include": "a.json" }"#;
+        let err = parse::<Value>(&dir.join("config.json"), config).unwrap_err();
+
+        assert_eq!(err.path, Some(dir.join("b.json")));
+        assert!(
+            err.message.ends_with("a.json is included recursively"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_parse_with_includes() {
+        let dir = dir("parse-with-includes");
+
+        fs::write(
+            dir.join("a.json"),
+            r#"{ "include": "b.json", "width": 20 }"#,
+        )
+        .unwrap();
+        fs::write(dir.join("b.json"), r#"{ "height": 10 }"#).unwrap();
+
+        let (_, includes): (Value, _) =
+            parse_with_includes(&dir.join("config.json"), r#"{ "include": "a.json" }"#).unwrap();
+
+        assert_eq!(includes, vec![dir.join("a.json"), dir.join("b.json")]);
+
+        // Configs without includes are parsed directly
+        let (_, includes): (Value, _) =
+            parse_with_includes(&dir.join("config.json"), r#"{ "width": 20 }"#).unwrap();
+
+        assert!(includes.is_empty());
+    }
+
+    #[test]
+    fn test_error_locations() {
+        let dir = dir("error-locations");
+
+        fs::write(dir.join("broken.json"), "{\n  \"width\": \n}").unwrap();
+        fs::write(
+            dir.join("colors.json"),
+            "{\n  \"bg\": \"#zzz\",\n  \"width\": 20\n}",
+        )
+        .unwrap();
+
+        // Syntax errors of included files
+        let err = parse::<Config>(&dir.join("config.json"), r#"{ "include": "broken.json" }"#)
+            .unwrap_err();
+
+        assert_eq!(err.path, Some(dir.join("broken.json")));
+        assert_eq!(err.location.map(|(line, _)| line), Some(3));
+
+        // Invalid values of included files
+        let config = "{\n  \"include\": \"colors.json\"\n}";
+        let err = parse::<Config>(&dir.join("config.json"), config).unwrap_err();
+
+        assert_eq!(err.path, Some(dir.join("colors.json")));
+        assert_eq!(err.location.map(|(line, _)| line), Some(2));
+
+        // Invalid values of the config itself
+        let config = "{\n  \"include\": \"colors.json\",\n  \"bg\": \"red\",\n  \"height\": -1\n}";
+        let err = parse::<Config>(&dir.join("config.json"), config).unwrap_err();
+
+        assert_eq!(err.path, None);
+        assert_eq!(err.location.map(|(line, _)| line), Some(4));
+    }
+}
@@ -1,20 +1,33 @@
+use indexmap::IndexMap;
 use ratatui::style::Color;
 use serde::Deserialize;
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    action::Action,
+    action::{Action, MouseBindings},
+    cli::Overrides,
     fum::FumResult,
+    regexes::VAR_RE,
     widget::{ContainerFlex, Direction, FumWidget},
 };
 
 use super::{
+    compose,
     defaults::{
-        align, bg, direction, fg, flex, height, keybinds, layout, players, use_active_player, width,
+        align, art_cache_size, bg, breakpoints, chord_timeout, direction, fg, flex, follow_playing,
+        height, keybinds, layout, layouts, mouse, players, use_active_player, width,
     },
+    icons::IconsConfig,
     keybind::Keybind,
 };
 
+/// Name of the layout profile made of the top-level layout options.
+pub const DEFAULT_PROFILE: &str = "default";
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Align {
@@ -31,6 +44,8 @@ pub enum Align {
     BottomLeft,
     #[serde(rename = "bottom-right")]
     BottomRight,
+    /// Stretches width and height to the terminal.
+    Fill,
 }
 
 impl Align {
@@ -44,11 +59,51 @@ impl Align {
             "top-right" => Some(Self::TopRight),
             "bottom-left" => Some(Self::BottomLeft),
             "bottom-right" => Some(Self::BottomRight),
+            "fill" => Some(Self::Fill),
             _ => None,
         }
     }
 }
 
+/// A named layout, whatever it leaves out is taken from the top-level config.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LayoutProfile {
+    pub align: Option<Align>,
+    pub direction: Option<Direction>,
+    pub flex: Option<ContainerFlex>,
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    pub bg: Option<Color>,
+    pub fg: Option<Color>,
+    pub layout: Option<Vec<FumWidget>>,
+}
+
+/// Layout profile to use once the terminal is at least this big.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Breakpoint {
+    #[serde(default)]
+    pub min_width: u16,
+
+    #[serde(default)]
+    pub min_height: u16,
+
+    pub layout: String,
+}
+
+/// A layout profile with every field resolved.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile<'a> {
+    pub name: &'a str,
+    pub align: &'a Align,
+    pub direction: &'a Direction,
+    pub flex: &'a ContainerFlex,
+    pub width: u16,
+    pub height: u16,
+    pub bg: Color,
+    pub fg: Color,
+    pub layout: &'a [FumWidget],
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     #[serde(default = "players")]
@@ -57,9 +112,15 @@ pub struct Config {
     #[serde(default = "use_active_player")]
     pub use_active_player: bool,
 
+    #[serde(default = "follow_playing")]
+    pub follow_playing: bool,
+
     #[serde(default = "keybinds")]
     pub keybinds: HashMap<Keybind, Action>,
 
+    #[serde(default = "chord_timeout")]
+    pub chord_timeout: u64,
+
     #[serde(default = "align")]
     pub align: Align,
 
@@ -84,8 +145,33 @@ pub struct Config {
     #[serde(default = "layout")]
     pub layout: Vec<FumWidget>,
 
+    #[serde(default = "layouts")]
+    pub layouts: IndexMap<String, LayoutProfile>,
+
+    #[serde(default = "breakpoints")]
+    pub breakpoints: Vec<Breakpoint>,
+
+    #[serde(default = "mouse")]
+    pub mouse: MouseBindings,
+
+    #[serde(default = "art_cache_size")]
+    pub art_cache_size: u64,
+
+    #[serde(default)]
+    pub icons: IconsConfig,
+
     #[serde(skip)]
     pub authorize: bool,
+
+    #[serde(skip)]
+    pub path: PathBuf,
+
+    /// Files the config includes, watched for changes along with it.
+    #[serde(skip)]
+    pub includes: Vec<PathBuf>,
+
+    #[serde(skip)]
+    pub overrides: Overrides,
 }
 
 impl Default for Config {
@@ -93,7 +179,9 @@ impl Default for Config {
         Self {
             players: players(),
             use_active_player: use_active_player(),
+            follow_playing: follow_playing(),
             keybinds: keybinds(),
+            chord_timeout: chord_timeout(),
             align: align(),
             direction: direction(),
             flex: flex(),
@@ -102,7 +190,15 @@ impl Default for Config {
             bg: bg(),
             fg: fg(),
             layout: layout(),
+            layouts: layouts(),
+            breakpoints: breakpoints(),
+            mouse: mouse(),
+            art_cache_size: art_cache_size(),
+            icons: IconsConfig::default(),
             authorize: false,
+            path: PathBuf::new(),
+            includes: Vec::new(),
+            overrides: Overrides::default(),
         }
     }
 }
@@ -111,12 +207,105 @@ impl Config {
     pub fn load(path: &PathBuf) -> FumResult<Self> {
         match fs::read_to_string(path) {
             Ok(config_file) => {
-                let config: Config = serde_json::from_str(&config_file)
-                    .map_err(|err| format!("Failed to parse config: {err}"))?;
+                let (mut config, includes): (Config, _) =
+                    compose::parse_with_includes(path, &config_file)
+                        .map_err(|err| format!("Failed to parse config: {err}"))?;
+                config.includes = includes;
 
                 Ok(config)
             }
-            Err(_) => Ok(Config::default()),
+            // No config file at all is fine
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(format!("Failed to read config: {err}").into()),
         }
     }
+
+    /// Loads the config again from the same file, keeping the command line options.
+    pub fn reload(&self) -> FumResult<Self> {
+        let mut config = Config::load(&self.path)?;
+
+        self.overrides.apply(&mut config);
+
+        config.path = self.path.clone();
+        config.overrides = self.overrides.clone();
+
+        Ok(config)
+    }
+
+    /// The config file followed by every file it includes.
+    pub fn files(&self) -> Vec<&Path> {
+        std::iter::once(self.path.as_path())
+            .chain(self.includes.iter().map(PathBuf::as_path))
+            .collect()
+    }
+
+    /// Names of the layout profiles in order, the top-level layout being "default".
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names = vec![DEFAULT_PROFILE];
+
+        names.extend(
+            self.layouts
+                .keys()
+                .map(|name| name.as_str())
+                .filter(|name| *name != DEFAULT_PROFILE),
+        );
+
+        names
+    }
+
+    pub fn profile(&self, name: &str) -> Option<Profile<'_>> {
+        let Some((name, profile)) = self.layouts.get_key_value(name) else {
+            if name != DEFAULT_PROFILE {
+                return None;
+            }
+
+            return Some(Profile {
+                name: DEFAULT_PROFILE,
+                align: &self.align,
+                direction: &self.direction,
+                flex: &self.flex,
+                width: self.width,
+                height: self.height,
+                bg: self.bg,
+                fg: self.fg,
+                layout: &self.layout,
+            });
+        };
+
+        Some(Profile {
+            name,
+            align: profile.align.as_ref().unwrap_or(&self.align),
+            direction: profile.direction.as_ref().unwrap_or(&self.direction),
+            flex: profile.flex.as_ref().unwrap_or(&self.flex),
+            width: profile.width.unwrap_or(self.width),
+            height: profile.height.unwrap_or(self.height),
+            bg: profile.bg.unwrap_or(self.bg),
+            fg: profile.fg.unwrap_or(self.fg),
+            layout: profile.layout.as_deref().unwrap_or(&self.layout),
+        })
+    }
+
+    /// The biggest breakpoint the terminal size satisfies.
+    pub fn breakpoint(&self, width: u16, height: u16) -> Option<&Breakpoint> {
+        self.breakpoints
+            .iter()
+            .filter(|breakpoint| width >= breakpoint.min_width && height >= breakpoint.min_height)
+            .max_by_key(|breakpoint| (breakpoint.min_width, breakpoint.min_height))
+    }
+
+    /// Names of the variables that are declared somewhere in any of the layouts.
+    pub fn var_names(&self) -> HashSet<String> {
+        self.profile_names()
+            .into_iter()
+            .filter_map(|name| self.profile(name))
+            .flat_map(|profile| profile.layout.iter())
+            .flat_map(|widget| widget.texts())
+            .flat_map(|text| {
+                VAR_RE
+                    .captures_iter(text)
+                    .map(|captures| captures[1].to_string())
+                    .collect::<Vec<String>>()
+            })
+            .collect()
+    }
 }
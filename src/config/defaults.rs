@@ -1,14 +1,18 @@
 use std::collections::HashMap;
 
+use indexmap::IndexMap;
 use ratatui::style::Color;
 
 use crate::{
-    action::Action,
+    action::{Action, MouseBindings},
     utils::etc::generate_btn_id,
-    widget::{ContainerFlex, CoverArtResize, Direction, FumWidget, LabelAlignment, ProgressOption},
+    widget::{
+        ContainerBorder, ContainerFlex, CoverArtResize, Direction, FumWidget, LabelAlignment,
+        ProgressOption, Size, Spacing, TextStyle, TruncatePosition,
+    },
 };
 
-use super::{keybind::Keybind, Align};
+use super::{keybind::Keybind, Align, Breakpoint, LayoutProfile};
 
 pub fn players() -> Vec<String> {
     vec!["spotify".to_string()]
@@ -16,6 +20,9 @@ pub fn players() -> Vec<String> {
 pub fn use_active_player() -> bool {
     true
 }
+pub fn follow_playing() -> bool {
+    false
+}
 pub fn align() -> Align {
     Align::Center
 }
@@ -37,6 +44,12 @@ pub fn bg() -> Color {
 pub fn fg() -> Color {
     Color::Reset
 }
+pub fn art_cache_size() -> u64 {
+    100
+}
+pub fn chord_timeout() -> u64 {
+    1000
+}
 pub fn keybinds() -> HashMap<Keybind, Action> {
     HashMap::from([
         (Keybind::Char('q'), Action::Quit),
@@ -49,105 +62,192 @@ pub fn keybinds() -> HashMap<Keybind, Action> {
         (Keybind::Char('c'), Action::ClearVote),
     ])
 }
+pub fn mouse() -> MouseBindings {
+    MouseBindings::default()
+}
+pub fn layouts() -> IndexMap<String, LayoutProfile> {
+    IndexMap::new()
+}
+pub fn breakpoints() -> Vec<Breakpoint> {
+    Vec::new()
+}
 pub fn layout() -> Vec<FumWidget> {
     Vec::from([
         FumWidget::CoverArt {
             width: None,
-            height: Some(10),
+            height: Some(Size::Length(10)),
             resize: CoverArtResize::Scale,
             bg: None,
             fg: None,
+            style: TextStyle::default(),
+            mouse: MouseBindings::default(),
         },
         FumWidget::Container {
             width: None,
-            height: Some(10),
+            height: Some(Size::Length(10)),
             direction: Direction::Vertical,
             flex: ContainerFlex::default(),
             bg: None,
             fg: None,
+            border: ContainerBorder::None,
+            border_fg: None,
+            title: None,
+            title_align: LabelAlignment::default(),
+            padding: Spacing::default(),
+            margin: Spacing::default(),
+            style: TextStyle::default(),
+            mouse: MouseBindings::default(),
             children: Vec::from([
                 FumWidget::Label {
+                    id: generate_btn_id(),
                     text: "$title".to_string(),
+                    size: None,
                     align: LabelAlignment::Center,
                     truncate: true,
+                    truncate_position: TruncatePosition::End,
+                    ellipsis: "...".to_string(),
+                    scroll: false,
+                    scroll_speed: 4.0,
+                    scroll_pause: 1500,
+                    scroll_separator: "   ".to_string(),
                     bg: None,
                     fg: None,
+                    style: TextStyle::default(),
+                    mouse: MouseBindings::default(),
                 },
                 FumWidget::Label {
+                    id: generate_btn_id(),
                     text: "$artists".to_string(),
+                    size: None,
                     align: LabelAlignment::Center,
                     truncate: true,
+                    truncate_position: TruncatePosition::End,
+                    ellipsis: "...".to_string(),
+                    scroll: false,
+                    scroll_speed: 4.0,
+                    scroll_pause: 1500,
+                    scroll_separator: "   ".to_string(),
                     bg: None,
                     fg: None,
+                    style: TextStyle::default(),
+                    mouse: MouseBindings::default(),
                 },
                 FumWidget::Container {
                     width: None,
-                    height: Some(1),
+                    height: Some(Size::Length(1)),
                     direction: Direction::Horizontal,
                     flex: ContainerFlex::SpaceAround,
                     bg: None,
                     fg: None,
+                    border: ContainerBorder::None,
+                    border_fg: None,
+                    title: None,
+                    title_align: LabelAlignment::default(),
+                    padding: Spacing::default(),
+                    margin: Spacing::default(),
+                    style: TextStyle::default(),
+                    mouse: MouseBindings::default(),
                     children: Vec::from([
                         FumWidget::Button {
                             id: generate_btn_id(),
-                            text: "󰒮".to_string(),
+                            text: "$prev_icon".to_string(),
+                            size: None,
                             action: Some(Action::Prev),
                             exec: None,
                             bg: None,
                             fg: None,
+                            style: TextStyle::default(),
+                            mouse: MouseBindings::default(),
                         },
                         FumWidget::Button {
                             id: generate_btn_id(),
                             text: "$status_icon".to_string(),
+                            size: None,
                             action: Some(Action::PlayPause),
                             exec: None,
                             bg: None,
                             fg: None,
+                            style: TextStyle::default(),
+                            mouse: MouseBindings::default(),
                         },
                         FumWidget::Button {
                             id: generate_btn_id(),
-                            text: "󰒭".to_string(),
+                            text: "$next_icon".to_string(),
+                            size: None,
                             action: Some(Action::Next),
                             exec: None,
                             bg: None,
                             fg: None,
+                            style: TextStyle::default(),
+                            mouse: MouseBindings::default(),
                         },
                     ]),
                 },
                 FumWidget::Progress {
+                    id: generate_btn_id(),
                     size: None,
                     progress: ProgressOption {
-                        char: '󰝤',
+                        char: None,
                         bg: None,
                         fg: None,
                     },
                     empty: ProgressOption {
-                        char: '󰁱',
+                        char: None,
                         bg: None,
                         fg: None,
                     },
+                    style: TextStyle::default(),
+                    mouse: MouseBindings::default(),
                 },
                 FumWidget::Container {
                     width: None,
-                    height: Some(1),
+                    height: Some(Size::Length(1)),
                     direction: Direction::Horizontal,
                     flex: ContainerFlex::SpaceBetween,
                     bg: None,
                     fg: None,
+                    border: ContainerBorder::None,
+                    border_fg: None,
+                    title: None,
+                    title_align: LabelAlignment::default(),
+                    padding: Spacing::default(),
+                    margin: Spacing::default(),
+                    style: TextStyle::default(),
+                    mouse: MouseBindings::default(),
                     children: Vec::from([
                         FumWidget::Label {
+                            id: generate_btn_id(),
                             text: "$position".to_string(),
+                            size: None,
                             align: LabelAlignment::Left,
                             truncate: false,
+                            truncate_position: TruncatePosition::End,
+                            ellipsis: "...".to_string(),
+                            scroll: false,
+                            scroll_speed: 4.0,
+                            scroll_pause: 1500,
+                            scroll_separator: "   ".to_string(),
                             bg: None,
                             fg: None,
+                            style: TextStyle::default(),
+                            mouse: MouseBindings::default(),
                         },
                         FumWidget::Label {
+                            id: generate_btn_id(),
                             text: "$length".to_string(),
+                            size: None,
                             align: LabelAlignment::Right,
                             truncate: false,
+                            truncate_position: TruncatePosition::End,
+                            ellipsis: "...".to_string(),
+                            scroll: false,
+                            scroll_speed: 4.0,
+                            scroll_pause: 1500,
+                            scroll_separator: "   ".to_string(),
                             bg: None,
                             fg: None,
+                            style: TextStyle::default(),
+                            mouse: MouseBindings::default(),
                         },
                     ]),
                 },
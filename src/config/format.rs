@@ -0,0 +1,219 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+
+/// File formats a config can be written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// Why a config couldn't be parsed, and where if the format tells.
+#[derive(Debug)]
+pub struct ParseError {
+    /// The included file the error is in, `None` for the config itself.
+    pub path: Option<PathBuf>,
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "{} at line {line} column {column}", self.message)?,
+            None => write!(f, "{}", self.message)?,
+        }
+
+        match &self.path {
+            Some(path) => write!(f, " of {}", path.display()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Format {
+    /// Picks the format by the file extension, anything unknown is JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Format::Toml,
+            Some("yaml" | "yml") => Format::Yaml,
+            _ => Format::Json,
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, file: &str) -> Result<T, ParseError> {
+        match self {
+            Format::Json => serde_json::from_str(file).map_err(|err| {
+                // The location is kept separately
+                let suffix = format!(" at line {} column {}", err.line(), err.column());
+                let message = err.to_string();
+
+                ParseError {
+                    path: None,
+                    location: Some((err.line(), err.column())),
+                    message: message
+                        .strip_suffix(&suffix)
+                        .unwrap_or(&message)
+                        .to_string(),
+                }
+            }),
+            Format::Toml => toml::from_str(file).map_err(|err| ParseError {
+                path: None,
+                location: err.span().map(|span| line_column(file, span.start)),
+                message: err.message().to_string(),
+            }),
+            Format::Yaml => serde_yaml::from_str(file).map_err(|err| {
+                let location = err.location().map(|loc| (loc.line(), loc.column()));
+                let message = err.to_string();
+
+                // Same as for JSON, the location is appended to the message
+                let message = match location {
+                    Some((line, column)) => message
+                        .strip_suffix(&format!(" at line {line} column {column}"))
+                        .unwrap_or(&message)
+                        .to_string(),
+                    None => message,
+                };
+
+                ParseError {
+                    path: None,
+                    location,
+                    message,
+                }
+            }),
+        }
+    }
+}
+
+/// Line and column (both starting at 1) of the byte offset.
+fn line_column(file: &str, offset: usize) -> (usize, usize) {
+    let before = file.get(..offset).unwrap_or(file);
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+
+    (line, column)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        action::{Action, Binding},
+        config::{keybind::Keybind, Config},
+        widget::{FumWidget, Size},
+    };
+
+    fn check_config(config: Config) {
+        assert!(matches!(config.keybinds[&Keybind::Char('q')], Action::Quit));
+        assert!(matches!(
+            config.keybinds[&Keybind::Char(' ')],
+            Action::PlayPause
+        ));
+
+        assert!(matches!(
+            config.mouse.on_scroll_up,
+            Some(Binding::Action(Action::VolumeUp(5)))
+        ));
+        assert!(matches!(
+            config.mouse.on_right_click,
+            Some(Binding::Full {
+                action: Some(Action::PlayPause),
+                exec: Some(_)
+            })
+        ));
+
+        let FumWidget::Container {
+            height,
+            mouse,
+            children,
+            ..
+        } = &config.layout[1]
+        else {
+            panic!("Expected a container, got {:?}", config.layout[1]);
+        };
+
+        assert_eq!(*height, Some(Size::Ratio(1, 3)));
+        assert!(matches!(
+            mouse.on_scroll_down,
+            Some(Binding::Full {
+                action: None,
+                exec: Some(_)
+            })
+        ));
+        assert!(matches!(
+            children[0],
+            FumWidget::Label {
+                size: Some(Size::Length(1)),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let config = r#"
+            width = 20
+            height = 18
+
+            [keybinds]
+            q = "quit()"
+            space = "play_pause()"
+
+            [mouse]
+            on_scroll_up = "volume_up(5)"
+            on_right_click = { action = "play_pause()", exec = "notify-send $title" }
+
+            [[layout]]
+            type = "cover-art"
+            height = 10
+
+            [[layout]]
+            type = "container"
+            height = "1/3"
+            direction = "vertical"
+            on_scroll_down = { exec = "notify-send $artist" }
+
+            [[layout.children]]
+            type = "label"
+            text = "$title"
+            size = 1
+        "#;
+
+        check_config(Format::Toml.parse(config).unwrap());
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let config = r#"
+width: 20
+height: 18
+
+keybinds:
+  q: quit()
+  space: play_pause()
+
+mouse:
+  on_scroll_up: volume_up(5)
+  on_right_click: { action: play_pause(), exec: notify-send $title }
+
+layout:
+  - type: cover-art
+    height: 10
+  - type: container
+    height: 1/3
+    direction: vertical
+    on_scroll_down: { exec: notify-send $artist }
+    children:
+      - type: label
+        text: $title
+        size: 1
+"#;
+
+        check_config(Format::Yaml.parse(config).unwrap());
+    }
+}
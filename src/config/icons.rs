@@ -0,0 +1,156 @@
+use mpris::{LoopStatus, PlaybackStatus};
+use serde::Deserialize;
+
+/// Built-in icon sets, `nerd` needs a patched Nerd Font.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconPreset {
+    #[default]
+    Nerd,
+    Unicode,
+    Ascii,
+}
+
+impl IconPreset {
+    pub fn from_str(str: &str) -> Option<Self> {
+        match str {
+            "nerd" => Some(Self::Nerd),
+            "unicode" => Some(Self::Unicode),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+
+    pub fn icons(&self) -> Icons {
+        let (play, pause, stop, prev, next) = match self {
+            Self::Nerd => ("󰐊", "󰏤", "󰓛", "󰒮", "󰒭"),
+            Self::Unicode => ("▶", "⏸", "■", "⏮", "⏭"),
+            Self::Ascii => (">", "||", "[]", "|<", ">|"),
+        };
+
+        let (shuffle_on, shuffle_off, loop_none, loop_track, loop_playlist) = match self {
+            Self::Nerd => ("󰒟", "󰒞", "󰑗", "󰑘", "󰑖"),
+            Self::Unicode => ("⤮", "→", "⇥", "↻¹", "↻"),
+            Self::Ascii => ("S", "-", "-", "R1", "R"),
+        };
+
+        let (progress, progress_empty, cover_art) = match self {
+            Self::Nerd => ('󰝤', '󰁱', "󰝚"),
+            Self::Unicode => ('█', '░', "♫"),
+            Self::Ascii => ('#', '-', "[art]"),
+        };
+
+        Icons {
+            play: play.to_string(),
+            pause: pause.to_string(),
+            stop: stop.to_string(),
+            prev: prev.to_string(),
+            next: next.to_string(),
+            shuffle_on: shuffle_on.to_string(),
+            shuffle_off: shuffle_off.to_string(),
+            loop_none: loop_none.to_string(),
+            loop_track: loop_track.to_string(),
+            loop_playlist: loop_playlist.to_string(),
+            progress,
+            progress_empty,
+            cover_art: cover_art.to_string(),
+        }
+    }
+}
+
+/// The `icons` section of the config, a preset with some of its glyphs replaced.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IconsConfig {
+    pub preset: IconPreset,
+    pub play: Option<String>,
+    pub pause: Option<String>,
+    pub stop: Option<String>,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    pub shuffle_on: Option<String>,
+    pub shuffle_off: Option<String>,
+    pub loop_none: Option<String>,
+    pub loop_track: Option<String>,
+    pub loop_playlist: Option<String>,
+    pub progress: Option<char>,
+    pub progress_empty: Option<char>,
+    pub cover_art: Option<String>,
+}
+
+impl IconsConfig {
+    pub fn icons(&self) -> Icons {
+        let preset = self.preset.icons();
+
+        Icons {
+            play: self.play.clone().unwrap_or(preset.play),
+            pause: self.pause.clone().unwrap_or(preset.pause),
+            stop: self.stop.clone().unwrap_or(preset.stop),
+            prev: self.prev.clone().unwrap_or(preset.prev),
+            next: self.next.clone().unwrap_or(preset.next),
+            shuffle_on: self.shuffle_on.clone().unwrap_or(preset.shuffle_on),
+            shuffle_off: self.shuffle_off.clone().unwrap_or(preset.shuffle_off),
+            loop_none: self.loop_none.clone().unwrap_or(preset.loop_none),
+            loop_track: self.loop_track.clone().unwrap_or(preset.loop_track),
+            loop_playlist: self.loop_playlist.clone().unwrap_or(preset.loop_playlist),
+            progress: self.progress.unwrap_or(preset.progress),
+            progress_empty: self.progress_empty.unwrap_or(preset.progress_empty),
+            cover_art: self.cover_art.clone().unwrap_or(preset.cover_art),
+        }
+    }
+}
+
+/// Every glyph fum draws by itself, in the icon variables such as `$shuffle_icon`,
+/// the default layout, progress bars without their own chars and the cover art placeholder.
+#[derive(Debug, Clone)]
+pub struct Icons {
+    pub play: String,
+    pub pause: String,
+    pub stop: String,
+    pub prev: String,
+    pub next: String,
+    pub shuffle_on: String,
+    pub shuffle_off: String,
+    pub loop_none: String,
+    pub loop_track: String,
+    pub loop_playlist: String,
+    pub progress: char,
+    pub progress_empty: char,
+    pub cover_art: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        IconPreset::default().icons()
+    }
+}
+
+impl Icons {
+    /// Icon of the playback status, which is what the play/pause button would do, e.g. pause while playing.
+    pub fn status(&self, status: &PlaybackStatus) -> &str {
+        match status {
+            PlaybackStatus::Stopped => &self.stop,
+            PlaybackStatus::Playing => &self.pause,
+            PlaybackStatus::Paused => &self.play,
+        }
+    }
+
+    /// Icon of the shuffle state, empty when the player doesn't support shuffling.
+    pub fn shuffle(&self, shuffle: Option<bool>) -> &str {
+        match shuffle {
+            Some(true) => &self.shuffle_on,
+            Some(false) => &self.shuffle_off,
+            None => "",
+        }
+    }
+
+    /// Icon of the loop status, empty when the player doesn't support looping.
+    pub fn loop_status(&self, loop_status: Option<LoopStatus>) -> &str {
+        match loop_status {
+            Some(LoopStatus::None) => &self.loop_none,
+            Some(LoopStatus::Track) => &self.loop_track,
+            Some(LoopStatus::Playlist) => &self.loop_playlist,
+            None => "",
+        }
+    }
+}
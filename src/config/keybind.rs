@@ -1,4 +1,4 @@
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use serde::{de, Deserialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -9,6 +9,7 @@ pub enum Keybind {
     Up,
     Right,
     Down,
+    Home,
     End,
     PageUp,
     PageDown,
@@ -20,6 +21,8 @@ pub enum Keybind {
     Caps,
     F(u8),
     Char(char),
+    Modified(KeyModifiers, Box<Keybind>),
+    Chord(Vec<Keybind>),
     Many(Vec<Keybind>)
 }
 
@@ -28,7 +31,7 @@ impl<'de> Deserialize<'de> for Keybind {
     where
         D: serde::Deserializer<'de>
     {
-        let keybind: &str = Deserialize::deserialize(deserializer)?;
+        let keybind: String = Deserialize::deserialize(deserializer)?;
 
         if keybind.contains(";") {
             let keybinds = keybind
@@ -40,35 +43,131 @@ impl<'de> Deserialize<'de> for Keybind {
             return Ok(Keybind::Many(keybinds));
         }
 
-        Keybind::parse_keybind(keybind)
+        Keybind::parse_keybind(&keybind)
     }
 }
 
 impl Keybind {
-    pub fn into_keycode(&self) -> KeyCode {
+    pub fn to_keycode(&self) -> Option<KeyCode> {
         match self {
-            Keybind::Backspace           => KeyCode::Backspace,
-            Keybind::Enter               => KeyCode::Enter,
-            Keybind::Left                => KeyCode::Left,
-            Keybind::Up                  => KeyCode::Up,
-            Keybind::Right               => KeyCode::Right,
-            Keybind::Down                => KeyCode::Down,
-            Keybind::End                 => KeyCode::End,
-            Keybind::PageUp              => KeyCode::PageUp,
-            Keybind::PageDown            => KeyCode::PageDown,
-            Keybind::Tab                 => KeyCode::Tab,
-            Keybind::BackTab             => KeyCode::BackTab,
-            Keybind::Delete              => KeyCode::Delete,
-            Keybind::Insert              => KeyCode::Insert,
-            Keybind::Esc                 => KeyCode::Esc,
-            Keybind::Caps                => KeyCode::CapsLock,
-            Keybind::F(u8)               => KeyCode::F(*u8),
-            Keybind::Char(char)          => KeyCode::Char(*char),
-            Keybind::Many(_)             => unreachable!()
+            Keybind::Backspace           => Some(KeyCode::Backspace),
+            Keybind::Enter               => Some(KeyCode::Enter),
+            Keybind::Left                => Some(KeyCode::Left),
+            Keybind::Up                  => Some(KeyCode::Up),
+            Keybind::Right               => Some(KeyCode::Right),
+            Keybind::Down                => Some(KeyCode::Down),
+            Keybind::Home                => Some(KeyCode::Home),
+            Keybind::End                 => Some(KeyCode::End),
+            Keybind::PageUp              => Some(KeyCode::PageUp),
+            Keybind::PageDown            => Some(KeyCode::PageDown),
+            Keybind::Tab                 => Some(KeyCode::Tab),
+            Keybind::BackTab             => Some(KeyCode::BackTab),
+            Keybind::Delete              => Some(KeyCode::Delete),
+            Keybind::Insert              => Some(KeyCode::Insert),
+            Keybind::Esc                 => Some(KeyCode::Esc),
+            Keybind::Caps                => Some(KeyCode::CapsLock),
+            Keybind::F(u8)               => Some(KeyCode::F(*u8)),
+            Keybind::Char(char)          => Some(KeyCode::Char(*char)),
+            Keybind::Modified(_, _)      => None,
+            Keybind::Chord(_)            => None,
+            Keybind::Many(_)             => None
+        }
+    }
+
+    /// Every alternative of this keybind, as a sequence of key presses.
+    pub fn sequences(&self) -> Vec<&[Keybind]> {
+        match self {
+            Keybind::Many(keybinds) => keybinds.iter().flat_map(|k| k.sequences()).collect(),
+            Keybind::Chord(keybinds) => vec![keybinds.as_slice()],
+            keybind => vec![std::slice::from_ref(keybind)],
+        }
+    }
+
+    /// Whether a single key press matches this key, modifiers must match exactly.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let (code, modifiers) = Keybind::normalize(key);
+
+        match self {
+            Keybind::Modified(keybind_modifiers, keybind) => {
+                keybind.to_keycode() == Some(code) && *keybind_modifiers == modifiers
+            }
+            keybind => keybind.to_keycode() == Some(code) && modifiers.is_empty(),
+        }
+    }
+
+    /// Shift is already part of the char (or of BackTab), so it's dropped from the modifiers.
+    fn normalize(key: &KeyEvent) -> (KeyCode, KeyModifiers) {
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SUPER | KeyModifiers::SHIFT);
+
+        match key.code {
+            KeyCode::Char(char) if shift => (KeyCode::Char(char.to_ascii_uppercase()), modifiers - KeyModifiers::SHIFT),
+            KeyCode::BackTab => (KeyCode::BackTab, modifiers - KeyModifiers::SHIFT),
+            code => (code, modifiers)
         }
     }
 
     fn parse_keybind<D>(keybind: &str) -> Result<Keybind, D>
+    where
+        D: de::Error
+    {
+        // A lone space is the space key rather than an empty chord
+        if keybind == " " {
+            return Ok(Keybind::Char(' '));
+        }
+
+        let presses = keybind
+            .split_whitespace()
+            .map(Keybind::parse_press)
+            .collect::<Result<Vec<Keybind>, D>>()?;
+
+        match presses.len() {
+            0 => Err(de::Error::custom("Empty keybind")),
+            1 => Ok(presses.into_iter().next().unwrap()),
+            _ => Ok(Keybind::Chord(presses)),
+        }
+    }
+
+    fn parse_press<D>(press: &str) -> Result<Keybind, D>
+    where
+        D: de::Error
+    {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = press;
+
+        // Length check so that e.g. "ctrl++" still leaves "+" as the key
+        loop {
+            let (modifier, prefix) = match key {
+                k if k.len() > 5 && k.starts_with("ctrl+")  => (KeyModifiers::CONTROL, 5),
+                k if k.len() > 4 && k.starts_with("alt+")   => (KeyModifiers::ALT, 4),
+                k if k.len() > 6 && k.starts_with("shift+") => (KeyModifiers::SHIFT, 6),
+                k if k.len() > 6 && k.starts_with("super+") => (KeyModifiers::SUPER, 6),
+                _ => break
+            };
+
+            modifiers |= modifier;
+            key = &key[prefix..];
+        }
+
+        let key = match Keybind::parse_key(key)? {
+            Keybind::Char(char) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers -= KeyModifiers::SHIFT;
+                Keybind::Char(char.to_ascii_uppercase())
+            },
+            Keybind::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers -= KeyModifiers::SHIFT;
+                Keybind::BackTab
+            },
+            key => key
+        };
+
+        match modifiers.is_empty() {
+            true => Ok(key),
+            false => Ok(Keybind::Modified(modifiers, Box::new(key)))
+        }
+    }
+
+    fn parse_key<D>(keybind: &str) -> Result<Keybind, D>
     where
         D: de::Error
     {
@@ -79,6 +178,7 @@ impl Keybind {
             "up"            => Ok(Keybind::Up),
             "right"         => Ok(Keybind::Right),
             "down"          => Ok(Keybind::Down),
+            "home"          => Ok(Keybind::Home),
             "end"           => Ok(Keybind::End),
             "page_up"       => Ok(Keybind::PageUp),
             "page_down"     => Ok(Keybind::PageDown),
@@ -88,13 +188,16 @@ impl Keybind {
             "insert"        => Ok(Keybind::Insert),
             "caps"          => Ok(Keybind::Caps),
             "esc"           => Ok(Keybind::Esc),
-            k if k.starts_with('f') => {
+            "space"         => Ok(Keybind::Char(' ')),
+            // `;` itself separates keybinds
+            "semicolon"     => Ok(Keybind::Char(';')),
+            k if k.len() > 1 && k.starts_with('f') => {
                 match k[1..].parse::<u8>() {
                     Ok(fn_num) => Ok(Keybind::F(fn_num)),
                     Err(_) => Err(de::Error::custom("Invalid fn key format"))
                 }
             },
-            k if k.len() == 1 => {
+            k if k.chars().count() == 1 => {
                 match k.chars().next() {
                     Some(char) => Ok(Keybind::Char(char)),
                     None => Err(de::Error::custom(format!("Invalid keyboard key: {k}")))
@@ -104,3 +207,89 @@ impl Keybind {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(keybind: &str) -> Result<Keybind, serde_json::Error> {
+        serde_json::from_value(serde_json::Value::String(keybind.to_string()))
+    }
+
+    fn modified(modifiers: KeyModifiers, keybind: Keybind) -> Keybind {
+        Keybind::Modified(modifiers, Box::new(keybind))
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(parse("q").unwrap(), Keybind::Char('q'));
+        assert_eq!(parse("f").unwrap(), Keybind::Char('f'));
+        assert_eq!(parse("f12").unwrap(), Keybind::F(12));
+        assert_eq!(parse("page_up").unwrap(), Keybind::PageUp);
+        assert_eq!(parse("space").unwrap(), Keybind::Char(' '));
+        assert_eq!(parse(" ").unwrap(), Keybind::Char(' '));
+        assert_eq!(parse("semicolon").unwrap(), Keybind::Char(';'));
+
+        assert!(parse("").is_err());
+        assert!(parse("fx").is_err());
+        assert!(parse("nope").is_err());
+        assert!(parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn test_parse_modifiers() {
+        assert_eq!(parse("ctrl+c").unwrap(), modified(KeyModifiers::CONTROL, Keybind::Char('c')));
+        assert_eq!(parse("ctrl++").unwrap(), modified(KeyModifiers::CONTROL, Keybind::Char('+')));
+        assert_eq!(parse("ctrl+semicolon").unwrap(), modified(KeyModifiers::CONTROL, Keybind::Char(';')));
+        assert_eq!(
+            parse("ctrl+alt+left").unwrap(),
+            modified(KeyModifiers::CONTROL | KeyModifiers::ALT, Keybind::Left)
+        );
+
+        // Shift is folded into the key
+        assert_eq!(parse("shift+a").unwrap(), Keybind::Char('A'));
+        assert_eq!(parse("shift+tab").unwrap(), Keybind::BackTab);
+        assert_eq!(parse("ctrl+shift+a").unwrap(), modified(KeyModifiers::CONTROL, Keybind::Char('A')));
+        assert_eq!(parse("shift+up").unwrap(), modified(KeyModifiers::SHIFT, Keybind::Up));
+    }
+
+    #[test]
+    fn test_parse_chords() {
+        let keybind = parse("g g; ctrl+x s;x").unwrap();
+
+        assert_eq!(
+            keybind,
+            Keybind::Many(vec![
+                Keybind::Chord(vec![Keybind::Char('g'), Keybind::Char('g')]),
+                Keybind::Chord(vec![
+                    modified(KeyModifiers::CONTROL, Keybind::Char('x')),
+                    Keybind::Char('s'),
+                ]),
+                Keybind::Char('x'),
+            ])
+        );
+
+        let lengths: Vec<usize> = keybind.sequences().iter().map(|sequence| sequence.len()).collect();
+        assert_eq!(lengths, vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_matches_normalized_keys() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+
+        // Terminals report shifted chars with or without the shift modifier
+        assert!(Keybind::Char('A').matches(&key(KeyCode::Char('A'), KeyModifiers::SHIFT)));
+        assert!(Keybind::Char('A').matches(&key(KeyCode::Char('A'), KeyModifiers::NONE)));
+        assert!(Keybind::Char('A').matches(&key(KeyCode::Char('a'), KeyModifiers::SHIFT)));
+        assert!(!Keybind::Char('a').matches(&key(KeyCode::Char('a'), KeyModifiers::SHIFT)));
+        assert!(Keybind::BackTab.matches(&key(KeyCode::BackTab, KeyModifiers::SHIFT)));
+
+        // Modifiers have to match exactly
+        let ctrl_a = modified(KeyModifiers::CONTROL, Keybind::Char('a'));
+
+        assert!(ctrl_a.matches(&key(KeyCode::Char('a'), KeyModifiers::CONTROL)));
+        assert!(!ctrl_a.matches(&key(KeyCode::Char('a'), KeyModifiers::CONTROL | KeyModifiers::ALT)));
+        assert!(!Keybind::Char('a').matches(&key(KeyCode::Char('a'), KeyModifiers::CONTROL)));
+        assert!(Keybind::Up.matches(&key(KeyCode::Up, KeyModifiers::NONE)));
+    }
+}
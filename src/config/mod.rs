@@ -1,6 +1,12 @@
+mod check;
+mod compose;
+#[allow(clippy::module_inception)]
 mod config;
 mod defaults;
+mod format;
+mod icons;
 mod keybind;
 
+pub use check::check;
 pub use config::*;
-pub use keybind::*;
+pub use icons::{IconPreset, Icons};
@@ -1,83 +1,177 @@
 use core::error;
 use std::{
     io::{stdout, Stdout},
-    process::{Command, Stdio},
-    time::Duration,
+    sync::mpsc::{self as std_mpsc, Receiver, Sender},
+    time::{Duration, Instant},
 };
 
 use crossterm::{
-    event::{self, EnableMouseCapture, Event, KeyEventKind, MouseButton, MouseEventKind},
+    event::{self, EnableMouseCapture, Event, KeyEvent, KeyEventKind, MouseButton, MouseEventKind},
     execute,
 };
-use mpris::Player;
-use ratatui::{prelude::CrosstermBackend, Terminal};
+use notify::RecommendedWatcher;
+use ratatui::{layout::Rect, prelude::CrosstermBackend, Terminal};
 use ratatui_image::picker::Picker;
 
 use tokio::sync::mpsc;
 
 use crate::{
-    action::Action,
-    config::{Config, Keybind},
-    meta::Meta,
+    action::{Action, Binding},
+    art::{ArtCache, ArtLoader},
+    config::{Breakpoint, Config, DEFAULT_PROFILE},
+    listener::{self, PlayerUpdate},
+    meta::{CoverArt, Meta},
+    players::Players,
     state::FumState,
     ui::Ui,
-    utils,
+    utils, watcher,
+    widget::Slider,
     youtube::{YouTubeAction, YouTubeClient},
 };
 
 pub type FumResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
-pub struct Fum<'a> {
-    config: &'a Config,
+/// How long to wait for terminal events before updating everything else.
+const EVENT_POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How often the position is re-synced with the player, in case it doesn't emit Seeked signals.
+const POSITION_SYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often to scan for players while scans are failing or the bus can't be watched.
+const PLAYER_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait for the config file to settle after a change before reloading it.
+const CONFIG_RELOAD_DELAY: Duration = Duration::from_millis(200);
+
+pub struct Fum {
+    config: Config,
+    pub config_watcher: Option<RecommendedWatcher>,
+    pub config_updates: Receiver<()>,
+    pub config_updates_sender: Sender<()>,
+    pub config_changed: Option<Instant>,
     pub terminal: Terminal<CrosstermBackend<Stdout>>,
-    pub ui: Ui<'a>,
+    pub ui: Ui,
     pub picker: Picker,
-    pub player: Option<Player>,
+    pub art_loader: ArtLoader,
+    pub players: Players,
+    /// When to scan for players, set once one appears on the bus or a scan has failed.
+    pub players_rescan: Option<Instant>,
+    pub bus_watched: bool,
+    pub player_updates: Receiver<(String, PlayerUpdate)>,
+    pub player_updates_sender: Sender<(String, PlayerUpdate)>,
+    pub position_synced: Instant,
+    pub muted_volume: Option<f64>,
+    pub pending_keys: Vec<KeyEvent>,
+    pub pending_keys_since: Instant,
+    /// Actions of the pending keys, run if no longer chord follows in time.
+    pub pending_actions: Vec<Action>,
+    /// Last breakpoint matched by the terminal size.
+    pub breakpoint: Option<Breakpoint>,
     pub state: FumState,
     pub redraw: bool,
     pub youtube_action_sender: mpsc::Sender<YouTubeAction>,
     pub exit: bool,
 }
 
-impl<'a> Fum<'a> {
-    pub fn new(config: &'a Config) -> FumResult<Self> {
-        let player = Meta::get_player(&config).ok();
+impl Fum {
+    pub fn new(config: Config) -> FumResult<Self> {
+        let layout = match config.overrides.layout.as_ref() {
+            Some(layout) if config.profile(layout).is_none() => {
+                return Err(format!("Unknown layout: {layout}").into())
+            }
+            Some(layout) => layout.to_string(),
+            None => DEFAULT_PROFILE.to_string(),
+        };
 
         let picker = Picker::from_query_stdio()?;
 
-        let meta = match &player {
-            Some(player) => Meta::fetch(player, &picker, None).unwrap_or(Meta::default()),
-            None => Meta::default(),
-        };
+        let art_loader = ArtLoader::new(ArtCache::new(config.art_cache_size));
+
+        let (player_updates_sender, player_updates) = std_mpsc::channel();
+
+        // Without the bus watcher new players can only be found by scanning for them now and then
+        let bus_watched = listener::watch_bus(player_updates_sender.clone()).is_ok();
+
+        // Hot reload is best effort, e.g. the config directory may not exist
+        let (config_updates_sender, config_updates) = std_mpsc::channel();
+        let config_watcher = watcher::watch(&config.files(), config_updates_sender.clone()).ok();
 
         // Enable mouse capture
         execute!(stdout(), EnableMouseCapture)?;
 
+        let mut state = FumState::new(Meta::default());
+        state.layout = layout;
+        state.icons = config.icons.icons();
+
         let sender = YouTubeClient::get_handle();
 
-        Ok(Self {
+        let mut fum = Self {
             config,
+            config_watcher,
+            config_updates,
+            config_updates_sender,
+            config_changed: None,
             terminal: ratatui::init(),
-            ui: Ui::new(config),
+            ui: Ui::new(),
             picker,
-            player,
-            state: FumState::new(meta),
+            art_loader,
+            players: Players::new(),
+            players_rescan: None,
+            bus_watched,
+            player_updates,
+            player_updates_sender,
+            position_synced: Instant::now(),
+            muted_volume: None,
+            pending_keys: Vec::new(),
+            pending_keys_since: Instant::now(),
+            pending_actions: Vec::new(),
+            breakpoint: None,
+            state,
             redraw: true, // Draw at startup
             youtube_action_sender: sender,
             exit: false,
-        })
+        };
+
+        // An explicitly chosen layout wins over the breakpoints until a different one matches
+        let size = fum.terminal.size()?;
+
+        match fum.config.overrides.layout.is_some() {
+            true => fum.breakpoint = fum.config.breakpoint(size.width, size.height).cloned(),
+            false => fum.apply_breakpoint(size.width, size.height),
+        }
+
+        fum.scan_players();
+
+        Ok(fum)
     }
 
     pub fn run(&mut self) -> FumResult<()> {
         while !self.exit {
+            // Scrolling labels move on their own, even if nothing else changed
+            if self
+                .state
+                .next_frame
+                .is_some_and(|next_frame| next_frame <= Instant::now())
+            {
+                self.redraw = true;
+            }
+
             if self.redraw {
                 self.terminal.draw(|frame| {
-                    self.ui.draw(frame, &mut self.state);
+                    let profile = self
+                        .config
+                        .profile(&self.state.layout)
+                        .or(self.config.profile(DEFAULT_PROFILE))
+                        .expect("default layout profile always exists");
+
+                    self.ui.draw(frame, &profile, &mut self.state);
                     self.redraw = false;
                 })?;
             }
 
+            self.update_config();
             self.update_meta();
+            self.update_art();
             self.term_events()?;
         }
 
@@ -87,26 +181,51 @@ impl<'a> Fum<'a> {
     }
 
     fn term_events(&mut self) -> FumResult<()> {
-        if event::poll(Duration::from_millis(100))? {
+        self.expire_keys()?;
+
+        // Wakes up early for the next frame of scrolling labels, or when the pending chord expires
+        let chord_end = (!self.pending_keys.is_empty())
+            .then(|| self.pending_keys_since + Duration::from_millis(self.config.chord_timeout));
+
+        let timeout = match self.state.next_frame.into_iter().chain(chord_end).min() {
+            Some(wake_up) => wake_up
+                .saturating_duration_since(Instant::now())
+                .min(EVENT_POLL_TIMEOUT),
+            None => EVENT_POLL_TIMEOUT,
+        };
+
+        if event::poll(timeout)? {
             let event = event::read()?;
 
             match event {
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    for (keybind, action) in self.config.keybinds.iter() {
-                        match keybind {
-                            Keybind::Many(keybinds) => {
-                                for keybind in keybinds {
-                                    if key.code == keybind.into_keycode() {
-                                        Action::run(action, self)?;
-                                    }
-                                }
-                            }
-                            keybind => {
-                                if key.code == keybind.into_keycode() {
-                                    Action::run(action, self)?;
-                                }
-                            }
+                    self.pending_keys.push(key);
+                    self.pending_keys_since = Instant::now();
+
+                    let (mut actions, mut pending) = self.match_keys(&self.pending_keys);
+
+                    // The pending chord went nowhere, run what its keys were bound to so far
+                    // and start over from the last key
+                    if actions.is_empty() && !pending && self.pending_keys.len() > 1 {
+                        for action in std::mem::take(&mut self.pending_actions) {
+                            Action::run(&action, self)?;
                         }
+
+                        self.pending_keys = vec![key];
+                        (actions, pending) = self.match_keys(&self.pending_keys);
+                    }
+
+                    // A longer chord could still follow, e.g. `g g` while `g` is bound too
+                    if pending {
+                        self.pending_actions = actions;
+                        return Ok(());
+                    }
+
+                    self.pending_keys.clear();
+                    self.pending_actions.clear();
+
+                    for action in actions {
+                        Action::run(&action, self)?;
                     }
                 }
                 Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
@@ -121,18 +240,74 @@ impl<'a> Fum<'a> {
                         }
 
                         if let Some(exec) = exec {
-                            let parts: Vec<&str> = exec.split_whitespace().collect();
-                            if let Some(command) = parts.get(0) {
-                                let _ = Command::new(command) // Ignore result
-                                    .args(&parts[1..])
-                                    .stdout(Stdio::null())
-                                    .stderr(Stdio::null())
-                                    .spawn();
+                            Action::run(&Action::Exec(exec), self)?;
+                        }
+                    } else if let Some((rect, slider)) =
+                        self.ui.slider(mouse.column, mouse.row, &self.state.sliders)
+                    {
+                        let rect = *rect;
+                        let ratio = Fum::slider_ratio(rect, mouse.column);
+
+                        match slider {
+                            // Nothing to seek in when the length is unknown
+                            Slider::Progress if self.state.meta.length.is_zero() => {}
+                            Slider::Progress => {
+                                // Seek once the button is released, dragging scrubs in the meantime
+                                let position = self.state.meta.length.mul_f64(ratio);
+                                self.state.scrub = Some((rect, position));
+                                self.redraw = true;
+                            }
+                            Slider::Volume { .. } => {
+                                let volume = (ratio * 100.0).round() as i64;
+                                Action::run(&Action::VolumeSet(volume), self)?;
                             }
                         }
                     }
                 }
-                Event::Resize(_, _) => {
+                Event::Mouse(mouse) if mouse.kind == MouseEventKind::Drag(MouseButton::Left) => {
+                    if let Some((rect, _)) = self.state.scrub {
+                        let ratio = Fum::slider_ratio(rect, mouse.column);
+                        let position = self.state.meta.length.mul_f64(ratio);
+
+                        self.state.scrub = Some((rect, position));
+                        self.redraw = true;
+                    }
+                }
+                Event::Mouse(mouse) if mouse.kind == MouseEventKind::Up(MouseButton::Left) => {
+                    if let Some((_, position)) = self.state.scrub.take() {
+                        self.seek_to(position);
+                        self.redraw = true;
+                    }
+                }
+                Event::Mouse(mouse)
+                    if matches!(
+                        mouse.kind,
+                        MouseEventKind::ScrollUp
+                            | MouseEventKind::ScrollDown
+                            | MouseEventKind::Down(MouseButton::Right)
+                            | MouseEventKind::Down(MouseButton::Middle)
+                    ) =>
+                {
+                    // Widget bindings first, then the sliders' own scrolling, then the global bindings
+                    if let Some(binding) = self.ui.mouse_binding(
+                        mouse.column,
+                        mouse.row,
+                        mouse.kind,
+                        &self.state.mouse_areas,
+                    ) {
+                        let binding = binding.clone();
+                        Binding::run(&binding, self)?;
+                    } else if let Some(action) =
+                        self.slider_scroll(mouse.column, mouse.row, mouse.kind)
+                    {
+                        Action::run(&action, self)?;
+                    } else if let Some(binding) = self.config.mouse.get(mouse.kind) {
+                        let binding = binding.clone();
+                        Binding::run(&binding, self)?;
+                    }
+                }
+                Event::Resize(width, height) => {
+                    self.apply_breakpoint(width, height);
                     self.redraw = true;
                 }
                 _ => {}
@@ -142,18 +317,350 @@ impl<'a> Fum<'a> {
         Ok(())
     }
 
+    /// Runs the actions of the pending keys once no longer chord followed within the chord timeout.
+    fn expire_keys(&mut self) -> FumResult<()> {
+        if self.pending_keys.is_empty()
+            || self.pending_keys_since.elapsed() < Duration::from_millis(self.config.chord_timeout)
+        {
+            return Ok(());
+        }
+
+        self.pending_keys.clear();
+
+        for action in std::mem::take(&mut self.pending_actions) {
+            Action::run(&action, self)?;
+        }
+
+        Ok(())
+    }
+
+    /// Actions of the keybinds fully matched by `keys`, and whether some chord could still be completed.
+    fn match_keys(&self, keys: &[KeyEvent]) -> (Vec<Action>, bool) {
+        let mut actions = Vec::new();
+        let mut pending = false;
+
+        for (keybind, action) in self.config.keybinds.iter() {
+            for sequence in keybind.sequences() {
+                if sequence.len() < keys.len() {
+                    continue;
+                }
+
+                let matches = sequence
+                    .iter()
+                    .zip(keys.iter())
+                    .all(|(keybind, key)| keybind.matches(key));
+
+                if !matches {
+                    continue;
+                }
+
+                if sequence.len() == keys.len() {
+                    actions.push(action.clone());
+                    break;
+                }
+
+                pending = true;
+            }
+        }
+
+        (actions, pending)
+    }
+
+    fn slider_scroll(&self, x: u16, y: u16, kind: MouseEventKind) -> Option<Action> {
+        let (_, slider) = self.ui.slider(x, y, &self.state.sliders)?;
+
+        match (slider, kind) {
+            (Slider::Volume { step }, MouseEventKind::ScrollUp) => Some(Action::VolumeUp(*step)),
+            (Slider::Volume { step }, MouseEventKind::ScrollDown) => {
+                Some(Action::VolumeDown(*step))
+            }
+            _ => None,
+        }
+    }
+
+    /// Position of `x` inside the slider, from 0.0 at its left edge to 1.0 at its right edge.
+    fn slider_ratio(rect: Rect, x: u16) -> f64 {
+        let offset = x.clamp(rect.x, rect.right().saturating_sub(1)) - rect.x;
+
+        offset as f64 / rect.width.saturating_sub(1).max(1) as f64
+    }
+
+    /// Seeks the active player, players that can't seek or reject the position are left alone.
+    fn seek_to(&mut self, position: Duration) {
+        if let (Some(player), Some(track_id)) = (self.players.active(), &self.state.meta.track_id) {
+            if !player.can_seek().unwrap_or(false) {
+                return;
+            }
+
+            if player.set_position(track_id.clone(), &position).is_ok() {
+                self.state.meta.sync_position(position);
+            }
+        }
+    }
+
+    fn scan_players(&mut self) {
+        let active = self
+            .players
+            .active_bus_name()
+            .map(|bus_name| bus_name.to_string());
+
+        match self.players.scan(&self.config) {
+            Ok(new) => {
+                for bus_name in new {
+                    listener::spawn(bus_name, self.player_updates_sender.clone());
+                }
+
+                self.players_rescan = match self.bus_watched {
+                    true => None,
+                    false => Some(Instant::now() + PLAYER_SCAN_INTERVAL),
+                };
+            }
+            // A failed scan says nothing about the players, keep them and try again later
+            Err(_) => self.players_rescan = Some(Instant::now() + PLAYER_SCAN_INTERVAL),
+        }
+
+        if self
+            .players
+            .active_bus_name()
+            .map(|bus_name| bus_name.to_string())
+            != active
+        {
+            self.player_changed();
+        }
+
+        self.sync_player_infos();
+    }
+
+    /// Copies the player infos into the state for the player-list widget.
+    fn sync_player_infos(&mut self) {
+        let active = self
+            .players
+            .active_bus_name()
+            .map(|bus_name| bus_name.to_string());
+
+        if self.state.players != self.players.infos || self.state.active_player != active {
+            self.state.players = self.players.infos.clone();
+            self.state.active_player = active;
+            self.redraw = true;
+        }
+    }
+
+    pub fn switch_layout(&mut self, name: &str) {
+        if self.config.profile(name).is_none() || self.state.layout == name {
+            return;
+        }
+
+        self.state.layout = name.to_string();
+
+        // Buttons of the previous layout would stay clickable otherwise
+        self.state.buttons.clear();
+        self.state.scrub = None;
+        self.redraw = true;
+    }
+
+    /// Switches to the layout profile of the breakpoint matching the terminal size, once it's a different
+    /// breakpoint than last time. A layout chosen in the meantime is kept until then.
+    fn apply_breakpoint(&mut self, width: u16, height: u16) {
+        let breakpoint = self.config.breakpoint(width, height).cloned();
+
+        if breakpoint == self.breakpoint {
+            return;
+        }
+
+        self.breakpoint = breakpoint.clone();
+
+        if let Some(breakpoint) = breakpoint {
+            self.switch_layout(&breakpoint.layout);
+        }
+    }
+
+    /// Switches to the next layout profile, in the order they're declared.
+    pub fn cycle_layout(&mut self) {
+        let names = self.config.profile_names();
+
+        let next = names
+            .iter()
+            .position(|name| *name == self.state.layout)
+            .map(|i| names[(i + 1) % names.len()])
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string();
+
+        self.switch_layout(&next);
+    }
+
+    /// Starts tracking and switches to a player that isn't tracked yet.
+    pub fn adopt_player(&mut self, name: &str) {
+        self.players.pin(name);
+        self.scan_players();
+
+        if self.players.select(name) {
+            self.player_changed();
+        }
+    }
+
+    /// Refetches everything after the active player was switched.
+    pub fn player_changed(&mut self) {
+        let meta = match self.players.active() {
+            Some(player) => Meta::fetch(player, None).unwrap_or_default(),
+            None => Meta::default(),
+        };
+
+        self.set_meta(meta);
+        self.position_synced = Instant::now();
+        self.redraw = true;
+    }
+
+    /// Swaps in the config once its file has changed, or shows why it couldn't be loaded.
+    fn update_config(&mut self) {
+        if self.config_updates.try_iter().count() > 0 {
+            self.config_changed = Some(Instant::now());
+        }
+
+        match self.config_changed {
+            Some(changed) if changed.elapsed() >= CONFIG_RELOAD_DELAY => self.config_changed = None,
+            _ => return,
+        }
+
+        // The file is being replaced, its creation will trigger another reload
+        if !self.config.path.exists() {
+            return;
+        }
+
+        match self.config.reload() {
+            Ok(config) => {
+                // Keep the values of the variables that are still used
+                let var_names = config.var_names();
+                self.state.vars.retain(|name, _| var_names.contains(name));
+
+                // Buttons of the old layout would stay clickable otherwise
+                self.state.buttons.clear();
+                self.state.config_error = None;
+                self.pending_keys.clear();
+                self.pending_actions.clear();
+
+                if config.art_cache_size != self.config.art_cache_size {
+                    self.art_loader.set_cache_size(config.art_cache_size);
+                }
+
+                // Includes may have been added or removed
+                if config.includes != self.config.includes {
+                    self.config_watcher =
+                        watcher::watch(&config.files(), self.config_updates_sender.clone()).ok();
+                }
+
+                self.state.icons = config.icons.icons();
+                self.config = config;
+
+                // The layout profile might have been removed
+                if self.config.profile(&self.state.layout).is_none() {
+                    self.state.layout = DEFAULT_PROFILE.to_string();
+                }
+
+                if let Ok(size) = self.terminal.size() {
+                    self.apply_breakpoint(size.width, size.height);
+                }
+            }
+            Err(err) => self.state.config_error = Some(err.to_string()),
+        }
+
+        self.redraw = true;
+    }
+
     fn update_meta(&mut self) {
-        if let Some(player) = &self.player {
-            let meta = Meta::fetch(player, &self.picker, Some(&self.state.meta))
-                .unwrap_or(Meta::default());
+        if self
+            .players_rescan
+            .is_some_and(|rescan| rescan <= Instant::now())
+        {
+            self.scan_players();
+        }
+
+        let updates: Vec<(String, PlayerUpdate)> = self.player_updates.try_iter().collect();
+
+        let mut refetch = false;
+        let mut active_changed = false;
+
+        for (bus_name, update) in updates {
+            let is_active = self.players.active_bus_name() == Some(bus_name.as_str());
+
+            match update {
+                PlayerUpdate::Appeared => self.players_rescan = Some(Instant::now()),
+                PlayerUpdate::Changed => {
+                    let started_playing = self.players.update_info(&bus_name);
+
+                    if is_active {
+                        refetch = true;
+                    } else if started_playing && self.config.follow_playing {
+                        active_changed |= self.players.select(&bus_name);
+                    }
+                }
+                PlayerUpdate::Seeked(position) if is_active => {
+                    self.state.meta.sync_position(position);
+                    self.position_synced = Instant::now();
+                    self.redraw = true;
+                }
+                PlayerUpdate::Seeked(_) => {}
+                PlayerUpdate::ShutDown => active_changed |= self.players.remove(&bus_name),
+            }
+        }
 
-            self.redraw = meta.changed;
-            self.state.meta = meta;
+        self.sync_player_infos();
 
+        if active_changed {
+            self.player_changed();
+            self.sync_player_infos();
             return;
         }
 
-        self.player = Meta::get_player(&self.config).ok();
-        self.state.meta = Meta::default();
+        let Some(player) = self.players.active() else {
+            return;
+        };
+
+        if refetch {
+            let meta = Meta::fetch(player, Some(&self.state.meta)).unwrap_or_default();
+
+            self.set_meta(meta);
+            self.position_synced = Instant::now();
+            self.redraw = true;
+
+            return;
+        }
+
+        if self.position_synced.elapsed() >= POSITION_SYNC_INTERVAL {
+            if let Ok(position) = Meta::get_position(player) {
+                self.state.meta.sync_position(position);
+            }
+
+            self.position_synced = Instant::now();
+        } else {
+            self.state.meta.interpolate();
+        }
+
+        if self.state.meta.changed {
+            self.redraw = true;
+        }
+    }
+
+    fn set_meta(&mut self, meta: Meta) {
+        if let (true, Some(art_url)) = (meta.cover_art_loading, &meta.art_url) {
+            self.art_loader.request(art_url);
+        }
+
+        self.state.meta = meta;
+    }
+
+    fn update_art(&mut self) {
+        for (url, image) in self.art_loader.try_iter() {
+            // Track changed while the art was loading
+            if self.state.meta.art_url.as_ref() != Some(&url) {
+                continue;
+            }
+
+            self.state.meta.cover_art = image.map(|image| CoverArt {
+                url,
+                image: self.picker.new_resize_protocol(image),
+            });
+            self.state.meta.cover_art_loading = false;
+            self.redraw = true;
+        }
     }
 }
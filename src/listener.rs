@@ -0,0 +1,106 @@
+use std::{sync::mpsc::Sender, thread, time::Duration};
+
+use dbus::blocking::Connection;
+use mpris::{Event, PlayerFinder};
+
+use crate::fum::FumResult;
+
+/// Prefix of the bus names of MPRIS players.
+const MPRIS_BUS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// How long the bus watcher blocks while waiting for a signal.
+const BUS_WATCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Update sent by a player listener into the main loop.
+#[derive(Debug)]
+pub enum PlayerUpdate {
+    /// A player appeared on the bus, it has to be scanned for before it can be listened to.
+    Appeared,
+    /// Something besides the position changed (track, status, shuffle, ...), metadata needs to be refetched.
+    Changed,
+    /// Player seeked, carries the new position.
+    Seeked(Duration),
+    /// Player has quit, the listener has stopped.
+    ShutDown,
+}
+
+/// Spawns a thread that listens for PropertiesChanged and Seeked signals of the player on `bus_name`.
+/// Updates are tagged with the bus name. The thread stops when the player quits or when the receiving
+/// end of `sender` is dropped.
+pub fn spawn(bus_name: String, sender: Sender<(String, PlayerUpdate)>) {
+    thread::spawn(move || {
+        // mpris::Player is not Send, so the listener needs its own connection to the player
+        let player = PlayerFinder::new()
+            .ok()
+            .and_then(|finder| finder.find_all().ok())
+            .and_then(|players| players.into_iter().find(|p| p.bus_name() == bus_name));
+
+        let player = match player {
+            Some(player) => player,
+            None => {
+                let _ = sender.send((bus_name, PlayerUpdate::ShutDown));
+                return;
+            }
+        };
+
+        let events = match player.events() {
+            Ok(events) => events,
+            Err(_) => {
+                let _ = sender.send((bus_name, PlayerUpdate::ShutDown));
+                return;
+            }
+        };
+
+        for event in events {
+            let update = match event {
+                Ok(Event::PlayerShutDown) | Err(_) => break,
+                Ok(Event::Seeked { position_in_us }) => {
+                    PlayerUpdate::Seeked(Duration::from_micros(position_in_us))
+                }
+                Ok(_) => PlayerUpdate::Changed,
+            };
+
+            // Receiver is gone, fum is no longer interested in this player
+            if sender.send((bus_name.clone(), update)).is_err() {
+                return;
+            }
+        }
+
+        let _ = sender.send((bus_name, PlayerUpdate::ShutDown));
+    });
+}
+
+/// Spawns a thread that listens for NameOwnerChanged signals of MPRIS bus names, so players started
+/// after fum are picked up without polling. The thread stops when the connection fails, or on the next
+/// signal once the receiving end of `sender` is dropped.
+pub fn watch_bus(sender: Sender<(String, PlayerUpdate)>) -> FumResult<()> {
+    let connection = Connection::new_session()?;
+
+    connection.add_match_no_cb(
+        "type='signal',sender='org.freedesktop.DBus',interface='org.freedesktop.DBus',\
+         member='NameOwnerChanged',arg0namespace='org.mpris.MediaPlayer2'",
+    )?;
+
+    thread::spawn(move || loop {
+        let message = match connection.channel().blocking_pop_message(BUS_WATCH_TIMEOUT) {
+            Ok(Some(message)) => message,
+            Ok(None) => continue,
+            Err(_) => return,
+        };
+
+        // An empty new owner means the player quit, its listener takes care of that
+        let Ok((bus_name, _, new_owner)) = message.read3::<String, String, String>() else {
+            continue;
+        };
+
+        if !bus_name.starts_with(MPRIS_BUS_PREFIX) || new_owner.is_empty() {
+            continue;
+        }
+
+        if sender.send((bus_name, PlayerUpdate::Appeared)).is_err() {
+            return;
+        }
+    });
+
+    Ok(())
+}
@@ -1,15 +1,20 @@
 // TODO: make urls and other optional player parameteres truly optional
 
 mod action;
+mod art;
 mod cli;
 mod config;
 mod fum;
+mod listener;
 mod meta;
+mod players;
 mod regexes;
 mod state;
+mod template;
 mod text;
 mod ui;
 mod utils;
+mod watcher;
 mod widget;
 mod youtube;
 
@@ -23,7 +28,7 @@ fn main() -> FumResult<()> {
         return Ok(());
     }
 
-    let mut fum = Fum::new(&config)?;
+    let mut fum = Fum::new(config)?;
 
     fum.run()?;
 
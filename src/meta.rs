@@ -1,17 +1,12 @@
-use std::{
-    fs,
-    io::{self, Cursor},
-    str::FromStr,
-    time::Duration,
-};
+use std::time::{Duration, Instant};
 
-use base64::{prelude::BASE64_STANDARD, Engine};
-use image::ImageReader;
-use mpris::{Metadata, MetadataValue, PlaybackStatus, Player, PlayerFinder, TrackID};
-use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
-use reqwest::{header::RANGE, Url};
+use mpris::{LoopStatus, Metadata, MetadataValue, PlaybackStatus, Player, TrackID};
+use ratatui_image::protocol::StatefulProtocol;
 
-use crate::{config::Config, fum::FumResult};
+use crate::fum::FumResult;
+
+/// Highest playback rate the position is advanced by.
+const MAX_RATE: f64 = 1000.0;
 
 #[derive(Clone)]
 pub struct CoverArt {
@@ -21,16 +16,30 @@ pub struct CoverArt {
 
 #[derive(Clone)]
 pub struct Meta {
+    pub player: String,
     pub metadata: Metadata,
     pub track_id: Option<TrackID>,
     pub title: String,
     pub artists: Vec<String>,
     pub album: String,
+    pub album_artists: Vec<String>,
+    pub track_number: Option<i32>,
+    pub disc_number: Option<i32>,
+    pub genres: Vec<String>,
+    pub year: Option<i32>,
+    pub user_rating: Option<f64>,
+    pub use_count: Option<i64>,
     pub status: PlaybackStatus,
-    pub status_icon: char,
     pub position: Duration,
+    pub position_updated: Instant,
     pub length: Duration,
+    pub volume: Option<f64>,
+    pub shuffle: Option<bool>,
+    pub loop_status: Option<LoopStatus>,
+    pub rate: Option<f64>,
+    pub art_url: Option<String>,
     pub cover_art: Option<CoverArt>,
+    pub cover_art_loading: bool,
     pub url: Option<String>,
     pub changed: bool,
 }
@@ -38,16 +47,30 @@ pub struct Meta {
 impl Default for Meta {
     fn default() -> Self {
         Self {
+            player: "No Player".to_string(),
             metadata: Metadata::default(),
             track_id: None,
             title: "No Music".to_string(),
             artists: vec!["Artist".to_string()],
             album: "Album".to_string(),
+            album_artists: Vec::new(),
+            track_number: None,
+            disc_number: None,
+            genres: Vec::new(),
+            year: None,
+            user_rating: None,
+            use_count: None,
             status: PlaybackStatus::Stopped,
-            status_icon: Meta::get_status_icon(&PlaybackStatus::Stopped),
             position: Duration::from_secs(0),
+            position_updated: Instant::now(),
             length: Duration::from_secs(0),
+            volume: None,
+            shuffle: None,
+            loop_status: None,
+            rate: None,
+            art_url: None,
             cover_art: None,
+            cover_art_loading: false,
             url: None,
             changed: false,
         }
@@ -55,18 +78,39 @@ impl Default for Meta {
 }
 
 impl Meta {
-    pub fn fetch(player: &Player, picker: &Picker, current: Option<&Self>) -> FumResult<Self> {
+    pub fn fetch(player: &Player, current: Option<&Self>) -> FumResult<Self> {
         let metadata = Meta::get_metadata(player)?;
         let track_id = Meta::get_trackid(&metadata);
         let title = Meta::get_title(&metadata)?;
         let artists = Meta::get_artists(&metadata)?;
         let album = Meta::get_album(&metadata)?;
+        let album_artists = Meta::get_album_artists(&metadata);
+        let track_number = metadata.track_number();
+        let disc_number = metadata.disc_number();
+        let genres = Meta::get_genres(&metadata);
+        let year = Meta::get_year(&metadata);
+        let user_rating = Meta::get_user_rating(&metadata);
+        let use_count = Meta::get_use_count(&metadata);
         let url = Meta::get_url(&metadata);
         let status = Meta::get_status(player)?;
-        let status_icon = Meta::get_status_icon(&status);
         let position = Meta::get_position(player)?;
         let length = Meta::get_length(&metadata)?;
-        let cover_art = Meta::get_cover_art(&metadata, picker, current)?;
+        let volume = player.get_volume().ok();
+
+        // Optional player properties, not every player supports them
+        let shuffle = player.get_shuffle().ok();
+        let loop_status = player.get_loop_status().ok();
+        let rate = player.get_playback_rate().ok();
+
+        let art_url = Meta::get_art_url(&metadata);
+
+        // Keep the current art if it didn't change, otherwise it has to be loaded again
+        let cover_art = current
+            .and_then(|current| current.cover_art.as_ref())
+            .filter(|current_art| Some(&current_art.url) == art_url.as_ref())
+            .cloned();
+
+        let cover_art_loading = art_url.is_some() && cover_art.is_none();
 
         let mut changed = false;
 
@@ -82,55 +126,69 @@ impl Meta {
         }
 
         Ok(Self {
+            player: player.identity().to_string(),
             metadata,
             track_id,
             title,
             artists,
             album,
+            album_artists,
+            track_number,
+            disc_number,
+            genres,
+            year,
+            user_rating,
+            use_count,
             status,
-            status_icon,
             position,
+            position_updated: Instant::now(),
             length,
+            volume,
+            shuffle,
+            loop_status,
+            rate,
+            art_url,
             cover_art,
+            cover_art_loading,
             url,
             changed,
         })
     }
 
-    pub fn get_player(config: &Config) -> FumResult<Player> {
-        let finder =
-            PlayerFinder::new().map_err(|err| format!("Failed to connect to D-Bus: {:?}.", err))?;
+    /// Sets the position reported by the player.
+    pub fn sync_position(&mut self, position: Duration) {
+        self.changed = position.as_secs() != self.position.as_secs();
+        self.position = position;
+        self.position_updated = Instant::now();
+    }
 
-        let players = finder
-            .find_all()
-            .map_err(|err| format!("There is no any active players: {:?}.", err))?;
+    /// Advances the position locally by the time elapsed since it was last updated, scaled by
+    /// the playback rate, so the player doesn't have to be asked for it on every tick.
+    pub fn interpolate(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.position_updated);
 
-        for player in players {
-            let identity = player.identity().to_lowercase();
-            let bus_name = player.bus_name();
+        self.position_updated = now;
+        self.changed = false;
 
-            if config
-                .players
-                .iter()
-                .any(|p| p.to_lowercase() == identity.to_lowercase() || bus_name.starts_with(p))
-            {
-                return Ok(player);
-            }
+        if self.status != PlaybackStatus::Playing {
+            return;
         }
 
-        // Find the most likely player to be used
-        if config.use_active_player {
-            let active = finder.find_active().map_err(|err| {
-                format!("'use-active-player' is set to true but failed to get active player: {err}")
-            })?;
+        // Players may report any rate, even infinite ones
+        let rate = self
+            .rate
+            .filter(|rate| rate.is_finite())
+            .unwrap_or(1.0)
+            .clamp(0.0, MAX_RATE);
+        let mut position = self.position.saturating_add(elapsed.mul_f64(rate));
 
-            return Ok(active);
+        if self.length > Duration::ZERO {
+            position = position.min(self.length);
         }
 
-        Err(Box::new(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to find any specified players",
-        )))
+        self.changed = position.as_secs() != self.position.as_secs();
+        self.position = position;
     }
 
     pub fn get_metadata(player: &Player) -> FumResult<Metadata> {
@@ -172,14 +230,6 @@ impl Meta {
         Ok(status)
     }
 
-    pub fn get_status_icon(status: &PlaybackStatus) -> char {
-        match status {
-            PlaybackStatus::Stopped => '󰓛',
-            PlaybackStatus::Playing => '󰏤',
-            PlaybackStatus::Paused => '󰐊',
-        }
-    }
-
     pub fn get_position(player: &Player) -> FumResult<Duration> {
         let position = player
             .get_position()
@@ -205,122 +255,131 @@ impl Meta {
         Ok(album)
     }
 
-    pub fn get_custom_meta(metadata: &Metadata, key: String) -> String {
-        let value = metadata.get(&key);
+    pub fn get_album_artists(metadata: &Metadata) -> Vec<String> {
+        metadata
+            .album_artists()
+            .map(|a| a.iter().map(|a| a.to_string()).collect())
+            .unwrap_or_default()
+    }
 
-        match value {
-            Some(value) => match value {
-                MetadataValue::String(str) => str.to_string(),
-                MetadataValue::Bool(bool) => bool.to_string(),
+    pub fn get_genres(metadata: &Metadata) -> Vec<String> {
+        match metadata.get("xesam:genre") {
+            Some(MetadataValue::String(genre)) => vec![genre.to_string()],
+            Some(genres) => genres
+                .as_str_array()
+                .map(|g| g.iter().map(|g| g.to_string()).collect())
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
 
-                MetadataValue::U8(u8) => u8.to_string(),
-                MetadataValue::U16(u16) => u16.to_string(),
-                MetadataValue::U32(u32) => u32.to_string(),
-                MetadataValue::U64(u64) => u64.to_string(),
+    /// Year of xesam:contentCreated, which is a date such as `2007-04-29T14:35:51`.
+    pub fn get_year(metadata: &Metadata) -> Option<i32> {
+        match metadata.get("xesam:contentCreated") {
+            Some(MetadataValue::String(created)) => created.split('-').next()?.trim().parse().ok(),
+            _ => None,
+        }
+    }
 
-                MetadataValue::I16(i16) => i16.to_string(),
-                MetadataValue::I32(i32) => i32.to_string(),
-                MetadataValue::I64(i64) => i64.to_string(),
+    pub fn get_user_rating(metadata: &Metadata) -> Option<f64> {
+        match metadata.get("xesam:userRating") {
+            Some(MetadataValue::F64(rating)) => Some(*rating),
+            _ => None,
+        }
+    }
 
-                MetadataValue::F64(f64) => f64.to_string(),
+    pub fn get_use_count(metadata: &Metadata) -> Option<i64> {
+        match metadata.get("xesam:useCount")? {
+            MetadataValue::I16(count) => Some((*count).into()),
+            MetadataValue::I32(count) => Some((*count).into()),
+            MetadataValue::I64(count) => Some(*count),
+            MetadataValue::U8(count) => Some((*count).into()),
+            MetadataValue::U16(count) => Some((*count).into()),
+            MetadataValue::U32(count) => Some((*count).into()),
+            MetadataValue::U64(count) => i64::try_from(*count).ok(),
+            _ => None,
+        }
+    }
 
-                MetadataValue::Unsupported | _ => "!Unsupported".to_string(),
-            },
+    pub fn get_custom_meta(metadata: &Metadata, key: String) -> String {
+        match metadata.get(&key) {
+            Some(value) => Meta::format_value(value),
             None => "!NotFound".to_string(),
         }
     }
 
-    pub fn get_cover_art(
-        metadata: &Metadata,
-        picker: &Picker,
-        current: Option<&Meta>,
-    ) -> FumResult<Option<CoverArt>> {
-        let art_url = if let Some(url) = metadata.get("mpris:artUrl") {
-            url
-        } else {
-            return Ok(None);
-        };
-
-        if let mpris::MetadataValue::String(art_url) = art_url {
-            if let Some(current) = &current {
-                if let Some(current_art) = &current.cover_art {
-                    if current_art.url == *art_url {
-                        return Ok(Some(current_art.clone()));
-                    }
-                }
-            }
+    /// Formats a metadata value, arrays are joined with commas.
+    fn format_value(value: &MetadataValue) -> String {
+        match value {
+            MetadataValue::String(str) => str.to_string(),
+            MetadataValue::Bool(bool) => bool.to_string(),
 
-            // Handle file:// scheme
-            if art_url.starts_with("file://") {
-                let art_path = Url::from_str(&art_url)
-                    .map_err(|err| format!("Failed to parse url: {art_url}: {err}"))?
-                    .to_file_path()
-                    .map_err(|_| format!("Failed to convert url: {art_url} to file_path"))?;
-
-                let bytes =
-                    fs::read(&art_path).map_err(|err| format!("Failed to read art file: {err}"))?;
-
-                let cover_art = ImageReader::new(Cursor::new(bytes))
-                    .with_guessed_format()
-                    .map_err(|_| "Unknown image file_type".to_string())?
-                    .decode()
-                    .map_err(|_| "Failed to decode image".to_string())?;
-
-                return Ok(Some(CoverArt {
-                    url: art_url.to_string(),
-                    image: picker.new_resize_protocol(cover_art),
-                }));
-            }
+            MetadataValue::U8(u8) => u8.to_string(),
+            MetadataValue::U16(u16) => u16.to_string(),
+            MetadataValue::U32(u32) => u32.to_string(),
+            MetadataValue::U64(u64) => u64.to_string(),
 
-            // Handle base64
-            if art_url.starts_with("data:") {
-                let base64_data = art_url
-                    .split_once("base64,")
-                    .ok_or("Invalid base64 url format")?
-                    .1;
-
-                let bytes = BASE64_STANDARD
-                    .decode(base64_data)
-                    .map_err(|err| format!("Failed to decode base64 data: {err}"))?;
-
-                let cover_art = ImageReader::new(Cursor::new(bytes))
-                    .with_guessed_format()
-                    .map_err(|_| "Unknown image file_type".to_string())?
-                    .decode()
-                    .map_err(|_| "Failed to decode image".to_string())?;
-
-                return Ok(Some(CoverArt {
-                    url: art_url.to_string(),
-                    image: picker.new_resize_protocol(cover_art),
-                }));
-            }
+            MetadataValue::I16(i16) => i16.to_string(),
+            MetadataValue::I32(i32) => i32.to_string(),
+            MetadataValue::I64(i64) => i64.to_string(),
+
+            MetadataValue::F64(f64) => f64.to_string(),
+
+            MetadataValue::Array(values) => values
+                .iter()
+                .map(Meta::format_value)
+                .collect::<Vec<String>>()
+                .join(", "),
+
+            _ => "!Unsupported".to_string(),
+        }
+    }
 
-            let client = reqwest::blocking::Client::new();
-            let resp = client
-                .get(art_url)
-                .header(RANGE, "bytes=0-1048576")
-                .send()
-                .map_err(|_| "Failed to fetch art url".to_string())?;
-
-            let bytes = resp
-                .bytes()
-                .map_err(|_| "Failed to get art image bytes".to_string())?;
-
-            let cover_art = ImageReader::new(Cursor::new(bytes))
-                .with_guessed_format()
-                .map_err(|_| "Unknown image file_type".to_string())?
-                .decode()
-                .map_err(|_| "Failed to decode image".to_string())?;
-
-            return Ok(Some(CoverArt {
-                url: art_url.to_string(),
-                image: picker.new_resize_protocol(cover_art),
-            }));
+    pub fn get_art_url(metadata: &Metadata) -> Option<String> {
+        match metadata.get("mpris:artUrl") {
+            Some(MetadataValue::String(art_url)) if !art_url.is_empty() => Some(art_url.to_string()),
+            _ => None,
         }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A playing track that was at 10s two seconds ago.
+    fn playing(rate: Option<f64>) -> Meta {
+        Meta {
+            status: PlaybackStatus::Playing,
+            length: Duration::from_secs(60),
+            position: Duration::from_secs(10),
+            position_updated: Instant::now() - Duration::from_secs(2),
+            rate,
+            ..Meta::default()
+        }
+    }
+
+    fn interpolated(rate: Option<f64>) -> Duration {
+        let mut meta = playing(rate);
+        meta.interpolate();
+
+        meta.position
+    }
+
+    #[test]
+    fn test_interpolate_scales_by_rate() {
+        let position = interpolated(Some(1.5)).as_secs_f64();
+        assert!((13.0..13.1).contains(&position), "{position}");
+
+        let position = interpolated(None).as_secs_f64();
+        assert!((12.0..12.1).contains(&position), "{position}");
+    }
 
-        Err(Box::new(io::Error::new(
-            io::ErrorKind::Other,
-            "mpris:artUrl is not a string.",
-        )))
+    #[test]
+    fn test_interpolate_clamps_rate() {
+        assert_eq!(interpolated(Some(-2.0)), Duration::from_secs(10));
+        assert_eq!(interpolated(Some(f64::MAX)), Duration::from_secs(60));
+        assert_eq!(interpolated(Some(f64::INFINITY)).as_secs(), 12);
+        assert_eq!(interpolated(Some(f64::NAN)).as_secs(), 12);
     }
 }
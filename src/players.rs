@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+
+use mpris::{FindingError, PlaybackStatus, Player, PlayerFinder};
+
+use crate::{config::Config, fum::FumResult};
+
+/// Summary of a running player, used to list every player regardless of whether it's tracked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerInfo {
+    pub bus_name: String,
+    pub identity: String,
+    pub status: PlaybackStatus,
+    pub title: String,
+}
+
+impl PlayerInfo {
+    pub fn from_player(player: &Player) -> Self {
+        let title = player
+            .get_metadata()
+            .ok()
+            .and_then(|metadata| metadata.title().map(|t| t.to_string()))
+            .unwrap_or_default();
+
+        Self {
+            bus_name: player.bus_name().to_string(),
+            identity: player.identity().to_string(),
+            status: player
+                .get_playback_status()
+                .unwrap_or(PlaybackStatus::Stopped),
+            title,
+        }
+    }
+}
+
+/// Every tracked player and the one currently being controlled.
+pub struct Players {
+    pub list: Vec<Player>,
+    pub active: Option<usize>,
+    pub infos: Vec<PlayerInfo>,
+    /// Running players that aren't tracked, kept to refresh their infos.
+    untracked: Vec<Player>,
+    /// Bus names of the players that have a listener.
+    listened: HashSet<String>,
+    pinned: Vec<String>,
+}
+
+impl Players {
+    pub fn new() -> Self {
+        Self {
+            list: Vec::new(),
+            active: None,
+            infos: Vec::new(),
+            untracked: Vec::new(),
+            listened: HashSet::new(),
+            pinned: Vec::new(),
+        }
+    }
+
+    pub fn active(&self) -> Option<&Player> {
+        self.active.and_then(|i| self.list.get(i))
+    }
+
+    pub fn active_bus_name(&self) -> Option<&str> {
+        self.active().map(|player| player.bus_name())
+    }
+
+    /// Finds the players to track and the rest of the running players.
+    /// Having no players to track is not an error, failing to talk to D-Bus is.
+    pub fn find(config: &Config, pinned: &[String]) -> FumResult<(Vec<Player>, Vec<Player>)> {
+        let finder =
+            PlayerFinder::new().map_err(|err| format!("Failed to connect to D-Bus: {:?}.", err))?;
+
+        let players = finder
+            .find_all()
+            .map_err(|err| format!("Failed to list the players: {:?}.", err))?;
+
+        let (matching, mut others): (Vec<Player>, Vec<Player>) = players
+            .into_iter()
+            .partition(|player| {
+                Players::matches(player, &config.players)
+                    || pinned.iter().any(|name| Players::is_named(player, name))
+            });
+
+        if !matching.is_empty() || !config.use_active_player {
+            return Ok((matching, others));
+        }
+
+        // Find the most likely player to be used
+        let active = match finder.find_active() {
+            Ok(active) => active,
+            Err(FindingError::NoPlayerFound) => return Ok((matching, others)),
+            Err(err) => {
+                return Err(format!(
+                    "'use-active-player' is set to true but failed to get active player: {err}"
+                )
+                .into())
+            }
+        };
+
+        let Some(i) = others.iter().position(|p| p.bus_name() == active.bus_name()) else {
+            return Ok((matching, others));
+        };
+
+        Ok((vec![others.remove(i)], others))
+    }
+
+    /// Syncs the lists with the currently running players, returns the bus names of the players that
+    /// don't have a listener yet. On error the lists are left as they are.
+    pub fn scan(&mut self, config: &Config) -> FumResult<Vec<String>> {
+        let (found, untracked) = Players::find(config, &self.pinned)?;
+        let active = self.active_bus_name().map(|bus_name| bus_name.to_string());
+
+        // Infos of known players are kept up to date by their listeners
+        let infos = found
+            .iter()
+            .chain(untracked.iter())
+            .map(|player| {
+                self.infos
+                    .iter()
+                    .find(|info| info.bus_name == player.bus_name())
+                    .cloned()
+                    .unwrap_or_else(|| PlayerInfo::from_player(player))
+            })
+            .collect();
+
+        let new: Vec<String> = found
+            .iter()
+            .chain(untracked.iter())
+            .map(|player| player.bus_name().to_string())
+            .filter(|bus_name| self.listened.insert(bus_name.clone()))
+            .collect();
+
+        self.list = found;
+        self.untracked = untracked;
+        self.infos = infos;
+        self.active = active
+            .and_then(|active| self.list.iter().position(|p| p.bus_name() == active))
+            .or(if self.list.is_empty() { None } else { Some(0) });
+
+        Ok(new)
+    }
+
+    /// Removes a player that has quit, returns true if it was the active one.
+    pub fn remove(&mut self, bus_name: &str) -> bool {
+        let was_active = self.active_bus_name() == Some(bus_name);
+        let active = self.active_bus_name().map(|bus_name| bus_name.to_string());
+
+        self.list.retain(|player| player.bus_name() != bus_name);
+        self.untracked.retain(|player| player.bus_name() != bus_name);
+        self.infos.retain(|info| info.bus_name != bus_name);
+        self.listened.remove(bus_name);
+
+        self.active = match was_active {
+            true if !self.list.is_empty() => Some(0),
+            true => None,
+            false => active.and_then(|active| self.list.iter().position(|p| p.bus_name() == active)),
+        };
+
+        was_active
+    }
+
+    pub fn next(&mut self) {
+        if let Some(active) = self.active {
+            self.active = Some((active + 1) % self.list.len());
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if let Some(active) = self.active {
+            self.active = Some((active + self.list.len() - 1) % self.list.len());
+        }
+    }
+
+    /// Selects the player by its identity or bus name, returns false if there is no such player.
+    /// An exact bus name wins over an identity, e.g. when clicking one of several instances.
+    pub fn select(&mut self, name: &str) -> bool {
+        let position = self
+            .list
+            .iter()
+            .position(|player| player.bus_name() == name)
+            .or_else(|| self.list.iter().position(|player| Players::is_named(player, name)));
+
+        match position {
+            Some(i) => {
+                self.active = Some(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Starts tracking a player that isn't in the players option, takes effect on the next scan.
+    pub fn pin(&mut self, name: &str) {
+        if !self.pinned.iter().any(|pinned| pinned == name) {
+            self.pinned.push(name.to_string());
+        }
+    }
+
+    /// Refreshes the info of the player, returns true if it just started playing.
+    pub fn update_info(&mut self, bus_name: &str) -> bool {
+        let player = self
+            .list
+            .iter()
+            .chain(self.untracked.iter())
+            .find(|p| p.bus_name() == bus_name);
+
+        let info = match player {
+            Some(player) => PlayerInfo::from_player(player),
+            None => return false,
+        };
+
+        let started_playing = info.status == PlaybackStatus::Playing;
+
+        match self.infos.iter_mut().find(|info| info.bus_name == bus_name) {
+            Some(current) => {
+                let was_playing = current.status == PlaybackStatus::Playing;
+                *current = info;
+
+                started_playing && !was_playing
+            }
+            None => {
+                self.infos.push(info);
+
+                started_playing
+            }
+        }
+    }
+
+    /// Whether the player is called `name`, unlike the `players` option a bus name has to match exactly.
+    fn is_named(player: &Player, name: &str) -> bool {
+        player.bus_name() == name
+            || player.identity().eq_ignore_ascii_case(name)
+            || player.bus_name_player_name_part().eq_ignore_ascii_case(name)
+    }
+
+    /// Whether the player is one of `names` from the `players` option, bus names match by prefix.
+    fn matches(player: &Player, names: &[String]) -> bool {
+        let identity = player.identity().to_lowercase();
+        let bus_name = player.bus_name();
+
+        names
+            .iter()
+            .any(|p| p.to_lowercase() == identity || bus_name.starts_with(p.as_str()))
+    }
+}
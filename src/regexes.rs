@@ -1,12 +1,36 @@
+use std::cmp::Reverse;
+
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::text::VARIABLES;
+
 lazy_static! {
     pub static ref FORWARD_RE: Regex = Regex::new(r"forward\((-?\d+)\)").unwrap();
     pub static ref BACKWARD_RE: Regex = Regex::new(r"backward\((-?\d+)\)").unwrap();
     pub static ref VAR_TOGGLE_RE: Regex = Regex::new(r"toggle\((\$\w[-\w]*),\s*(\$\w[-\w]*),\s*(\$\w[-\w]*)\)").unwrap();
+    pub static ref VOLUME_UP_RE: Regex = Regex::new(r"volume_up\((\d+)\)").unwrap();
+    pub static ref VOLUME_DOWN_RE: Regex = Regex::new(r"volume_down\((\d+)\)").unwrap();
+    pub static ref VOLUME_SET_RE: Regex = Regex::new(r"volume_set\((\d+)\)").unwrap();
+    pub static ref RATE_UP_RE: Regex = Regex::new(r"rate_up\((\d+(?:\.\d+)?)\)").unwrap();
+    pub static ref RATE_DOWN_RE: Regex = Regex::new(r"rate_down\((\d+(?:\.\d+)?)\)").unwrap();
+    pub static ref RATE_SET_RE: Regex = Regex::new(r"rate_set\((\d+(?:\.\d+)?)\)").unwrap();
+    pub static ref SELECT_PLAYER_RE: Regex = Regex::new(r"select_player\((.+)\)").unwrap();
     pub static ref VAR_SET_RE: Regex = Regex::new(r"set\((\$\w[-\w]*),\s*(\$\w[-\w]*)\)").unwrap();
+    pub static ref SWITCH_LAYOUT_RE: Regex = Regex::new(r"switch_layout\((.+)\)").unwrap();
+    pub static ref EXEC_RE: Regex = Regex::new(r"(?s)^exec\((.+)\)$").unwrap();
 
     pub static ref GET_META_RE: Regex = Regex::new(r"get_meta\((.*?)\)").unwrap();
     pub static ref VAR_RE: Regex = Regex::new(r"var\((\$\w+),\s*(\$\w+)\)").unwrap();
+
+    /// Anything replace_text substitutes in plain text, longer variables first so `$status` doesn't cut `$status_icon`.
+    pub static ref TEXT_VAR_RE: Regex = {
+        let mut variables = VARIABLES.to_vec();
+        variables.sort_by_key(|variable| Reverse(variable.len()));
+
+        let variables: Vec<String> = variables.iter().map(|variable| regex::escape(variable)).collect();
+
+        Regex::new(&format!(r"get_meta\((.*?)\)|var\((\$\w+),\s*(\$\w+)\)|({})", variables.join("|"))).unwrap()
+    };
+    pub static ref MARKUP_TAG_RE: Regex = Regex::new(r"<(/?)(b|i|u|dim|rev|fg|bg)(?:=([^<>]+))?>").unwrap();
 }
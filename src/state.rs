@@ -1,13 +1,42 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
+use mpris::TrackID;
 use ratatui::{layout::Rect, style::Color};
 
-use crate::{action::Action, meta::Meta, widget::Direction};
+use crate::{
+    action::{Action, MouseBindings},
+    config::{Icons, DEFAULT_PROFILE},
+    meta::Meta,
+    players::PlayerInfo,
+    widget::{Direction, Slider},
+};
+
+/// A scrolling label, kept while it's being drawn.
+pub struct Marquee {
+    pub started: Instant,
+    pub drawn: bool,
+    /// Track id and title it started scrolling on.
+    pub track: (Option<TrackID>, String),
+}
 
 pub struct FumState {
     pub meta: Meta,
     pub buttons: HashMap<String, (Rect, Option<Action>, Option<String>)>,
+    pub sliders: HashMap<String, (Rect, Slider)>,
+    pub mouse_areas: Vec<(Rect, MouseBindings)>,
     pub vars: HashMap<String, String>,
+    pub scrub: Option<(Rect, Duration)>,
+    pub players: Vec<PlayerInfo>,
+    pub active_player: Option<String>,
+    pub layout: String,
+    pub icons: Icons,
+    pub config_error: Option<String>,
+    /// Scrolling labels by their widget id.
+    pub marquees: HashMap<String, Marquee>,
+    pub next_frame: Option<Instant>,
     pub parent_direction: Direction,
     pub parent_bg: Color,
     pub parent_fg: Color,
@@ -18,10 +47,151 @@ impl FumState {
         Self {
             meta,
             buttons: HashMap::new(),
+            sliders: HashMap::new(),
+            mouse_areas: Vec::new(),
             vars: HashMap::new(),
+            scrub: None,
+            players: Vec::new(),
+            active_player: None,
+            layout: DEFAULT_PROFILE.to_string(),
+            icons: Icons::default(),
+            config_error: None,
+            marquees: HashMap::new(),
+            next_frame: None,
             parent_direction: Direction::default(),
             parent_bg: Color::Reset,
             parent_fg: Color::Reset,
         }
     }
+
+    /// Registers an area reacting to mouse bindings, later (inner) areas take precedence.
+    pub fn register_mouse(&mut self, area: Rect, mouse: &MouseBindings) {
+        if !mouse.is_empty() {
+            self.mouse_areas.push((area, mouse.clone()));
+        }
+    }
+
+    /// Offset in cells of the scrolling label `id`, `cycle` cells long with its separator, whose end is
+    /// visible at offset `end`. Also schedules a redraw for when the offset changes next. The label scrolls
+    /// from the start again once the track changes.
+    pub fn marquee_offset(
+        &mut self,
+        id: &str,
+        cycle: usize,
+        end: usize,
+        speed: f64,
+        pause: Duration,
+    ) -> usize {
+        let now = Instant::now();
+        let track = (self.meta.track_id.clone(), self.meta.title.clone());
+
+        let marquee = self
+            .marquees
+            .entry(id.to_string())
+            .or_insert_with(|| Marquee {
+                started: now,
+                drawn: true,
+                track: track.clone(),
+            });
+        marquee.drawn = true;
+
+        if marquee.track != track {
+            marquee.started = now;
+            marquee.track = track;
+        }
+
+        if cycle == 0 || !speed.is_finite() || speed <= 0.0 {
+            return 0;
+        }
+
+        let (offset, next) = marquee_frame(now - marquee.started, cycle, end, speed, pause);
+
+        // Speeds too slow to ever move on don't schedule anything
+        if let Ok(next) = Duration::try_from_secs_f64(next) {
+            if let Some(next_frame) = now.checked_add(next) {
+                self.next_frame = Some(self.next_frame.map_or(next_frame, |at| at.min(next_frame)));
+            }
+        }
+
+        offset
+    }
+
+    /// Current position, or the position being scrubbed to on the progress bar.
+    pub fn position(&self) -> Duration {
+        match self.scrub {
+            Some((_, position)) => position,
+            None => self.meta.position,
+        }
+    }
+}
+
+/// Offset of a marquee `elapsed` into scrolling, and the seconds until it changes. The marquee pauses at
+/// the start of the text and once its end is visible at offset `end`, then wraps around through the
+/// separator back to the start.
+fn marquee_frame(
+    elapsed: Duration,
+    cycle: usize,
+    end: usize,
+    speed: f64,
+    pause: Duration,
+) -> (usize, f64) {
+    let pause = pause.as_secs_f64();
+    let to_end = end as f64 / speed;
+    let elapsed = elapsed.as_secs_f64() % (2.0 * pause + cycle as f64 / speed);
+
+    // Time spent scrolling so far, without the pauses
+    let scrolling = match elapsed {
+        elapsed if elapsed < pause => return (0, pause - elapsed),
+        elapsed if elapsed < pause + to_end => elapsed - pause,
+        elapsed if elapsed < 2.0 * pause + to_end => return (end, 2.0 * pause + to_end - elapsed),
+        elapsed => elapsed - 2.0 * pause,
+    };
+
+    let scrolled = scrolling * speed;
+    let offset = scrolled.floor();
+
+    (offset as usize % cycle, (offset + 1.0 - scrolled) / speed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_marquee_frame() {
+        let frame = |millis| {
+            let (offset, next) = marquee_frame(
+                Duration::from_millis(millis),
+                10,
+                4,
+                2.0,
+                Duration::from_secs(1),
+            );
+            (offset, (next * 1000.0).round() as u64)
+        };
+
+        // Paused at the start, then 2 cells per second up to the end at offset 4
+        assert_eq!(frame(0), (0, 1000));
+        assert_eq!(frame(1250), (0, 250));
+        assert_eq!(frame(1500), (1, 500));
+        assert_eq!(frame(2999), (3, 1));
+
+        // Paused at the end, then on through the separator and around to the start again
+        assert_eq!(frame(3000), (4, 1000));
+        assert_eq!(frame(3500), (4, 500));
+        assert_eq!(frame(4000), (4, 500));
+        assert_eq!(frame(6500), (9, 500));
+        assert_eq!(frame(7000), (0, 1000));
+    }
+
+    #[test]
+    fn test_marquee_offset_with_tiny_speed() {
+        let mut state = FumState::new(Meta::default());
+
+        assert_eq!(
+            state.marquee_offset("label", 10, 4, f64::MIN_POSITIVE, Duration::ZERO),
+            0
+        );
+        assert_eq!(state.next_frame, None);
+    }
 }
@@ -0,0 +1,776 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+use crate::{utils::etc::truncate, widget::TruncatePosition};
+
+/// Value of a variable or an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    pub fn to_text(&self) -> String {
+        match self {
+            Self::Text(text) => text.to_string(),
+            Self::List(list) => list.join(", "),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Self::Text(text) => !text.is_empty(),
+            Self::List(list) => list.iter().any(|item| !item.is_empty()),
+        }
+    }
+
+    fn from_bool(value: bool) -> Self {
+        match value {
+            true => Self::Text("true".to_string()),
+            false => Self::Text(String::new()),
+        }
+    }
+
+    fn map(self, f: impl Fn(&str) -> String) -> Self {
+        match self {
+            Self::Text(text) => Self::Text(f(&text)),
+            Self::List(list) => Self::List(list.iter().map(|item| f(item)).collect()),
+        }
+    }
+}
+
+/// Gives the values of variables and receives the evaluated text, in order.
+pub trait Renderer<'a> {
+    /// Value of a variable, named with or without its `$`.
+    fn variable(&mut self, name: &str) -> Option<Value>;
+
+    /// Text written as is in the template.
+    fn literal(&mut self, text: &'a str);
+
+    /// Value of an expression.
+    fn value(&mut self, value: String);
+}
+
+/// Evaluates the `{...}` expressions and `{if ...}...{else}...{end}` conditionals of a text.
+/// Expressions that can't be parsed or use unknown variables are kept as they are, `\{` and `\}`
+/// stand for plain braces. Double braces are left to config templates.
+pub fn render<'a>(text: &'a str, renderer: &mut impl Renderer<'a>) {
+    render_nodes(&parse(text), renderer);
+}
+
+/// Expressions of a text that can't be parsed, with the reason.
+pub fn errors(text: &str) -> Vec<String> {
+    blocks(text)
+        .into_iter()
+        .filter_map(|block| match block {
+            Block::Invalid { raw, error } => Some(format!("`{raw}`: {error}")),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Names of the variables used by the expressions of a text.
+pub fn variables(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+
+    for block in blocks(text) {
+        if let Block::Expr { expr, .. } | Block::If(expr) = block {
+            expr.variables(&mut names);
+        }
+    }
+
+    names
+}
+
+/// Splits a text on whitespace, keeping expressions whole even if they contain spaces.
+pub fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut i = 0;
+
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+
+        if c.is_whitespace() {
+            if let Some(start) = start.take() {
+                words.push(&text[start..i]);
+            }
+
+            i += c.len_utf8();
+            continue;
+        }
+
+        start.get_or_insert(i);
+
+        i += match c {
+            '\\' if rest[1..].starts_with(['{', '}']) => 2,
+            '{' => closing_brace(rest).map_or(1, |end| end + 1),
+            c => c.len_utf8(),
+        };
+    }
+
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+
+    words
+}
+
+#[derive(Debug)]
+enum Block<'a> {
+    Literal(&'a str),
+    Expr { expr: Expr, raw: &'a str },
+    If(Expr),
+    Else(&'a str),
+    End(&'a str),
+    Invalid { raw: &'a str, error: String },
+}
+
+#[derive(Debug)]
+enum Node<'a> {
+    Literal(&'a str),
+    Expr {
+        expr: Expr,
+        raw: &'a str,
+    },
+    If {
+        condition: Expr,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+    },
+}
+
+/// An `{if}` whose `{end}` hasn't been reached yet.
+struct Branch<'a> {
+    condition: Expr,
+    then: Vec<Node<'a>>,
+    otherwise: Option<Vec<Node<'a>>>,
+}
+
+impl<'a> Branch<'a> {
+    fn nodes(&mut self) -> &mut Vec<Node<'a>> {
+        match &mut self.otherwise {
+            Some(otherwise) => otherwise,
+            None => &mut self.then,
+        }
+    }
+
+    fn into_node(self) -> Node<'a> {
+        Node::If {
+            condition: self.condition,
+            then: self.then,
+            otherwise: self.otherwise.unwrap_or_default(),
+        }
+    }
+}
+
+/// Splits a text into literal text and the blocks between braces.
+fn blocks(text: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+
+        if rest.starts_with("\\{") || rest.starts_with("\\}") {
+            // Drop the backslash, the brace starts the next literal
+            push_literal(&mut blocks, &text[literal_start..i]);
+            literal_start = i + 1;
+            i += 2;
+            continue;
+        }
+
+        let end = match rest.starts_with('{') {
+            true => closing_brace(rest),
+            false => None,
+        };
+
+        match end {
+            Some(end) => {
+                push_literal(&mut blocks, &text[literal_start..i]);
+                blocks.push(block(&rest[..=end]));
+                i += end + 1;
+                literal_start = i;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+
+    push_literal(&mut blocks, &text[literal_start..]);
+
+    blocks
+}
+
+fn push_literal<'a>(blocks: &mut Vec<Block<'a>>, text: &'a str) {
+    if !text.is_empty() {
+        blocks.push(Block::Literal(text));
+    }
+}
+
+/// Index of the brace closing the one `text` starts with, skipping quoted strings.
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '}') => return Some(i),
+            (None, '{') => return None,
+            (None, _) => {}
+        }
+    }
+
+    None
+}
+
+/// Parses the block between braces, `raw` includes the braces.
+fn block(raw: &str) -> Block<'_> {
+    let inner = raw[1..raw.len() - 1].trim();
+
+    let parsed = match inner {
+        "else" => return Block::Else(raw),
+        "end" => return Block::End(raw),
+        inner => match inner.strip_prefix("if") {
+            Some(condition) if condition.starts_with(char::is_whitespace) => {
+                Parser::parse(condition).map(Block::If)
+            }
+            _ => Parser::parse(inner).map(|expr| Block::Expr { expr, raw }),
+        },
+    };
+
+    parsed.unwrap_or_else(|error| Block::Invalid { raw, error })
+}
+
+fn parse(text: &str) -> Vec<Node<'_>> {
+    let mut root = Vec::new();
+    let mut branches: Vec<Branch> = Vec::new();
+
+    fn current<'a, 'b>(
+        root: &'b mut Vec<Node<'a>>,
+        branches: &'b mut [Branch<'a>],
+    ) -> &'b mut Vec<Node<'a>> {
+        match branches.last_mut() {
+            Some(branch) => branch.nodes(),
+            None => root,
+        }
+    }
+
+    for block in blocks(text) {
+        match block {
+            Block::Literal(text) | Block::Invalid { raw: text, .. } => {
+                current(&mut root, &mut branches).push(Node::Literal(text))
+            }
+            Block::Expr { expr, raw } => {
+                current(&mut root, &mut branches).push(Node::Expr { expr, raw })
+            }
+            Block::If(condition) => branches.push(Branch {
+                condition,
+                then: Vec::new(),
+                otherwise: None,
+            }),
+            Block::Else(raw) => match branches.last_mut() {
+                Some(branch) if branch.otherwise.is_none() => branch.otherwise = Some(Vec::new()),
+                _ => current(&mut root, &mut branches).push(Node::Literal(raw)),
+            },
+            Block::End(raw) => match branches.pop() {
+                Some(branch) => current(&mut root, &mut branches).push(branch.into_node()),
+                None => root.push(Node::Literal(raw)),
+            },
+        }
+    }
+
+    // Conditionals without an {end} last until the end of the text
+    while let Some(branch) = branches.pop() {
+        current(&mut root, &mut branches).push(branch.into_node());
+    }
+
+    root
+}
+
+fn render_nodes<'a>(nodes: &[Node<'a>], renderer: &mut impl Renderer<'a>) {
+    for node in nodes {
+        match node {
+            Node::Literal(text) => renderer.literal(text),
+            Node::Expr { expr, raw } => match expr.eval(renderer) {
+                Some(value) => renderer.value(value.to_text()),
+                None => renderer.literal(raw),
+            },
+            Node::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                // Unknown variables make the condition false
+                let truthy = condition
+                    .eval(renderer)
+                    .is_some_and(|value| value.is_truthy());
+
+                match truthy {
+                    true => render_nodes(then, renderer),
+                    false => render_nodes(otherwise, renderer),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(String),
+    Pipe,
+    Fallback,
+    Equal,
+    NotEqual,
+    Not,
+    Open,
+    Close,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(name) => write!(f, "`{name}`"),
+            Self::Str(text) => write!(f, "{text:?}"),
+            Self::Number(number) => write!(f, "`{number}`"),
+            Self::Pipe => write!(f, "`|`"),
+            Self::Fallback => write!(f, "`??`"),
+            Self::Equal => write!(f, "`==`"),
+            Self::NotEqual => write!(f, "`!=`"),
+            Self::Not => write!(f, "`!`"),
+            Self::Open => write!(f, "`(`"),
+            Self::Close => write!(f, "`)`"),
+            Self::Comma => write!(f, "`,`"),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '|' => Token::Pipe,
+            '?' if chars.next_if_eq(&'?').is_some() => Token::Fallback,
+            '=' if chars.next_if_eq(&'=').is_some() => Token::Equal,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::NotEqual,
+            '!' => Token::Not,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '"' | '\'' => Token::Str(string(c, &mut chars)?),
+            c if c.is_ascii_digit() => {
+                let mut number = c.to_string();
+
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let mut name = c.to_string();
+
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+                {
+                    name.push(c);
+                }
+
+                Token::Ident(name)
+            }
+            c => return Err(format!("Unexpected `{c}`")),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a string up to the closing quote, `\` escapes the next character.
+fn string(quote: char, chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut string = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => string.extend(chars.next()),
+            c if c == quote => return Ok(string),
+            c => string.push(c),
+        }
+    }
+
+    Err("Unterminated string".to_string())
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Upper,
+    Lower,
+    Trim,
+    First,
+    Join(String),
+    Truncate(usize, String),
+}
+
+impl Filter {
+    fn new(name: &str, args: Vec<String>) -> Result<Self, String> {
+        let filter = match (name, &args[..]) {
+            ("upper", []) => Self::Upper,
+            ("lower", []) => Self::Lower,
+            ("trim", []) => Self::Trim,
+            ("first", []) => Self::First,
+            ("join", []) => Self::Join(", ".to_string()),
+            ("join", [separator]) => Self::Join(separator.to_string()),
+            ("truncate", [width]) | ("truncate", [width, _]) => Self::Truncate(
+                width
+                    .parse()
+                    .map_err(|_| format!("Invalid width for truncate: {width}"))?,
+                args.get(1)
+                    .map_or("...", |ellipsis| ellipsis.as_str())
+                    .to_string(),
+            ),
+            ("upper" | "lower" | "trim" | "first" | "join" | "truncate", _) => {
+                return Err(format!("Wrong number of arguments for {name}"))
+            }
+            _ => return Err(format!("Unknown filter `{name}`")),
+        };
+
+        Ok(filter)
+    }
+
+    fn apply(&self, value: Value) -> Value {
+        match (self, value) {
+            (Self::Upper, value) => value.map(str::to_uppercase),
+            (Self::Lower, value) => value.map(str::to_lowercase),
+            (Self::Trim, value) => value.map(|text| text.trim().to_string()),
+            (Self::First, Value::List(list)) => {
+                Value::Text(list.into_iter().next().unwrap_or_default())
+            }
+            (Self::Join(separator), Value::List(list)) => Value::Text(list.join(separator)),
+            (Self::First | Self::Join(_), value) => value,
+            (Self::Truncate(width, ellipsis), value) => Value::Text(truncate(
+                &value.to_text(),
+                *width,
+                ellipsis,
+                &TruncatePosition::End,
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(String),
+    Variable(String),
+    Not(Box<Expr>),
+    Filter(Box<Expr>, Filter),
+    Fallback(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, bool, Box<Expr>),
+}
+
+impl Expr {
+    /// Value of the expression, None if it uses a variable the renderer doesn't know,
+    /// unless a fallback covers it.
+    fn eval<'a>(&self, renderer: &mut impl Renderer<'a>) -> Option<Value> {
+        let value = match self {
+            Self::Literal(text) => Value::Text(text.to_string()),
+            Self::Variable(name) => renderer.variable(name)?,
+            Self::Not(expr) => Value::from_bool(!expr.eval(renderer)?.is_truthy()),
+            Self::Filter(expr, filter) => filter.apply(expr.eval(renderer)?),
+            Self::Fallback(expr, fallback) => match expr.eval(renderer) {
+                Some(value) if value.is_truthy() => value,
+                _ => fallback.eval(renderer)?,
+            },
+            Self::Compare(left, equal, right) => {
+                let same = left.eval(renderer)?.to_text() == right.eval(renderer)?.to_text();
+
+                Value::from_bool(same == *equal)
+            }
+        };
+
+        Some(value)
+    }
+
+    fn variables(&self, names: &mut Vec<String>) {
+        match self {
+            Self::Literal(_) => {}
+            Self::Variable(name) => names.push(name.to_string()),
+            Self::Not(expr) | Self::Filter(expr, _) => expr.variables(names),
+            Self::Fallback(left, right) | Self::Compare(left, _, right) => {
+                left.variables(names);
+                right.variables(names);
+            }
+        }
+    }
+}
+
+/// Parses `condition := fallback (("==" | "!=") fallback)?`, `fallback := filtered ("??" filtered)*`,
+/// `filtered := unary ("|" filter)*` and `unary := "!"* (string | number | variable | "(" condition ")")`.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn parse(expr: &str) -> Result<Expr, String> {
+        let mut parser = Parser {
+            tokens: tokenize(expr)?,
+            position: 0,
+        };
+
+        if parser.tokens.is_empty() {
+            return Err("Empty expression".to_string());
+        }
+
+        let expr = parser.condition()?;
+
+        match parser.next() {
+            Some(token) => Err(format!("Unexpected {token}")),
+            None => Ok(expr),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        let matches = self.tokens.get(self.position) == Some(token);
+
+        if matches {
+            self.position += 1;
+        }
+
+        matches
+    }
+
+    fn condition(&mut self) -> Result<Expr, String> {
+        let left = self.fallback()?;
+
+        let equal = match self.tokens.get(self.position) {
+            Some(Token::Equal) => true,
+            Some(Token::NotEqual) => false,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+
+        let right = self.fallback()?;
+
+        Ok(Expr::Compare(Box::new(left), equal, Box::new(right)))
+    }
+
+    fn fallback(&mut self) -> Result<Expr, String> {
+        let mut expr = self.filtered()?;
+
+        while self.next_if(&Token::Fallback) {
+            expr = Expr::Fallback(Box::new(expr), Box::new(self.filtered()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn filtered(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+
+        while self.next_if(&Token::Pipe) {
+            let name = match self.next() {
+                Some(Token::Ident(name)) => name,
+                Some(token) => return Err(format!("Expected a filter, found {token}")),
+                None => return Err("Expected a filter".to_string()),
+            };
+
+            let args = match self.next_if(&Token::Open) {
+                true => self.args()?,
+                false => Vec::new(),
+            };
+
+            expr = Expr::Filter(Box::new(expr), Filter::new(&name, args)?);
+        }
+
+        Ok(expr)
+    }
+
+    /// Literal arguments of a filter, after the opening parenthesis.
+    fn args(&mut self) -> Result<Vec<String>, String> {
+        let mut args = Vec::new();
+
+        if self.next_if(&Token::Close) {
+            return Ok(args);
+        }
+
+        loop {
+            match self.next() {
+                Some(Token::Str(arg) | Token::Number(arg)) => args.push(arg),
+                Some(token) => return Err(format!("Expected a string or a number, found {token}")),
+                None => return Err("Expected a string or a number".to_string()),
+            }
+
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::Close) => return Ok(args),
+                Some(token) => return Err(format!("Expected `,` or `)`, found {token}")),
+                None => return Err("Expected `)`".to_string()),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Str(text) | Token::Number(text)) => Ok(Expr::Literal(text)),
+            Some(Token::Ident(name)) => Ok(Expr::Variable(name)),
+            Some(Token::Open) => {
+                let expr = self.condition()?;
+
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    Some(token) => Err(format!("Expected `)`, found {token}")),
+                    None => Err("Expected `)`".to_string()),
+                }
+            }
+            Some(token) => Err(format!("Unexpected {token}")),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct Output {
+        variables: HashMap<&'static str, Value>,
+        text: String,
+    }
+
+    impl Renderer<'_> for Output {
+        fn variable(&mut self, name: &str) -> Option<Value> {
+            self.variables.get(name.trim_start_matches('$')).cloned()
+        }
+
+        fn literal(&mut self, text: &str) {
+            self.text.push_str(text);
+        }
+
+        fn value(&mut self, value: String) {
+            self.text.push_str(&value);
+        }
+    }
+
+    fn eval(text: &str) -> String {
+        let mut output = Output {
+            variables: HashMap::from([
+                ("title", Value::Text("Song".to_string())),
+                ("album", Value::Text(String::new())),
+                ("status", Value::Text("Playing".to_string())),
+                (
+                    "artists",
+                    Value::List(vec!["First".to_string(), "Second".to_string()]),
+                ),
+            ]),
+            text: String::new(),
+        };
+
+        render(text, &mut output);
+
+        output.text
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(eval("{title} - {artists}"), "Song - First, Second");
+        assert_eq!(eval("{$title}"), "Song");
+        assert_eq!(eval("[{missing}]"), "[{missing}]");
+        assert_eq!(
+            eval("{missing|upper} {!missing}"),
+            "{missing|upper} {!missing}"
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(eval("{title|upper}"), "SONG");
+        assert_eq!(eval("{title | lower}"), "song");
+        assert_eq!(eval("{artists|join(\" & \")}"), "First & Second");
+        assert_eq!(eval("{artists|first|upper}"), "FIRST");
+        assert_eq!(eval("{artists|upper|join('/')}"), "FIRST/SECOND");
+        assert_eq!(eval("{\"  padded \"|trim}"), "padded");
+        assert_eq!(eval("{artists|join|truncate(8)}"), "First...");
+        assert_eq!(eval("{title|truncate(3, \"…\")}"), "So…");
+    }
+
+    #[test]
+    fn test_fallback() {
+        assert_eq!(eval("{album ?? \"Single\"}"), "Single");
+        assert_eq!(eval("{title ?? \"Single\"}"), "Song");
+        assert_eq!(eval("{missing ?? album ?? 'none'}"), "none");
+    }
+
+    #[test]
+    fn test_conditionals() {
+        assert_eq!(eval("{if status == \"Playing\"}on{else}off{end}"), "on");
+        assert_eq!(eval("{if status != 'Playing'}on{else}off{end}"), "off");
+        assert_eq!(eval("{if album}{album}{else}{title}{end}!"), "Song!");
+        assert_eq!(eval("{if !album}no album{end}"), "no album");
+        assert_eq!(eval("{if title}a{if album}b{else}c{end}d{end}"), "acd");
+        assert_eq!(eval("{if (album ?? title) == 'Song'}yes{end}"), "yes");
+    }
+
+    #[test]
+    fn test_unbalanced_conditionals() {
+        assert_eq!(eval("{if album}hidden"), "");
+        assert_eq!(eval("a{end}b{else}c"), "a{end}b{else}c");
+        assert_eq!(eval("{if title}a{else}b{else}c{end}"), "a");
+    }
+
+    #[test]
+    fn test_literal_braces() {
+        assert_eq!(eval("\\{title\\}"), "{title}");
+        assert_eq!(eval("\\{{title}\\}"), "{Song}");
+        assert_eq!(eval("{{title}}"), "{Song}");
+        assert_eq!(eval("{title|nope}"), "{title|nope}");
+        assert_eq!(eval("{}"), "{}");
+        assert_eq!(eval("{ unclosed"), "{ unclosed");
+        assert_eq!(eval("{'}'}"), "}");
+        assert_eq!(eval("日本{title}語"), "日本Song語");
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words(" a  b\tc "), vec!["a", "b", "c"]);
+        assert_eq!(
+            words("echo {artists|join(' & ')} x{title | upper}y"),
+            vec!["echo", "{artists|join(' & ')}", "x{title | upper}y"]
+        );
+        assert_eq!(words("\\{ a \\}"), vec!["\\{", "a", "\\}"]);
+        assert_eq!(words("{ unclosed"), vec!["{", "unclosed"]);
+    }
+
+    #[test]
+    fn test_errors_and_variables() {
+        assert_eq!(
+            errors("{title|nope} {album ??} {title}"),
+            vec![
+                "`{title|nope}`: Unknown filter `nope`".to_string(),
+                "`{album ??}`: Unexpected end of expression".to_string(),
+            ]
+        );
+        assert_eq!(
+            variables("{if status == 'Playing'}{title|upper}{end} {album ?? $mode}"),
+            vec!["status", "title", "album", "$mode"]
+        );
+    }
+}
@@ -1,45 +1,280 @@
+use std::str::FromStr;
+
+use ratatui::{style::{Color, Style, Stylize}, text::{Line, Span}};
 use regex::Captures;
 
-use crate::{meta::Meta, regexes::{GET_META_RE, VAR_RE}, state::FumState, utils::etc::{format_duration, format_remaining}};
+use crate::{meta::Meta, regexes::{MARKUP_TAG_RE, TEXT_VAR_RE}, state::FumState, template::{self, Renderer, Value}, utils::etc::{format_duration, format_rate, format_remaining, format_volume}};
+
+/// Every variable replace_text knows about, besides the ones declared with var().
+pub const VARIABLES: &[&str] = &["$title", "$artists", "$player", "$album", "$status", "$status_icon", "$volume", "$position", "$remaining-length", "$length", "$layout", "$album_artists", "$track_number", "$disc_number", "$genre", "$year", "$user_rating", "$use_count", "$shuffle", "$loop_status", "$rate", "$shuffle_icon", "$loop_icon", "$prev_icon", "$next_icon"];
+
+/// Collects the text of a template.
+struct PlainText<'s> {
+    state: &'s mut FumState,
+    text: String,
+}
+
+impl Renderer<'_> for PlainText<'_> {
+    fn variable(&mut self, name: &str) -> Option<Value> {
+        variable(name, self.state)
+    }
+
+    fn literal(&mut self, text: &str) {
+        self.text.push_str(&replace_variables(text, self.state));
+    }
+
+    fn value(&mut self, value: String) {
+        self.text.push_str(&value);
+    }
+}
+
+/// Collects the spans of a template with inline markup, styled by the tags opened so far.
+struct StyledText<'a, 's> {
+    state: &'s mut FumState,
+    spans: Vec<Span<'static>>,
+    tags: Vec<(&'a str, Style)>,
+}
+
+impl<'a> Renderer<'a> for StyledText<'a, '_> {
+    fn variable(&mut self, name: &str) -> Option<Value> {
+        variable(name, self.state)
+    }
+
+    fn literal(&mut self, text: &'a str) {
+        push_markup(&mut self.spans, &mut self.tags, text, self.state);
+    }
+
+    fn value(&mut self, value: String) {
+        push_span(&mut self.spans, value, &self.tags);
+    }
+}
 
+/// Evaluates the expressions of a text and replaces its variables.
 pub fn replace_text(text: &str, state: &mut FumState) -> String {
-    match text {
-        text if GET_META_RE.is_match(text) => {
-            GET_META_RE.replace_all(text, |c: &Captures| {
-                let key = c[1].to_string();
-                Meta::get_custom_meta(&state.meta.metadata, key)
-            }).to_string()
-        },
-
-        text if VAR_RE.is_match(text) => {
-            VAR_RE.replace_all(text, |c: &Captures| {
-                let mut vars = state.vars.clone();
-
-                let name = c[1].to_string();
-                let default_text = c[2].to_string();
-
-                match vars.get(&name) {
-                    Some(var) => return replace_text(var, state),
-                    None => {
-                        vars.insert(name, default_text.to_string());
-
-                        // Update state.vars
-                        state.vars = vars;
-
-                        return replace_text(&default_text, state);
-                    }
+    let mut plain = PlainText { state, text: String::new() };
+
+    template::render(text, &mut plain);
+
+    plain.text
+}
+
+/// Splits a command into its arguments before replacing the variables of each,
+/// so a value with spaces stays a single argument.
+pub fn replace_args(command: &str, state: &mut FumState) -> Vec<String> {
+    template::words(command).into_iter().map(|arg| replace_text(arg, state)).collect()
+}
+
+/// Replaces every `$variable`, get_meta() and var() of a text in one pass.
+fn replace_variables(text: &str, state: &mut FumState) -> String {
+    TEXT_VAR_RE.replace_all(text, |c: &Captures| {
+        if let Some(key) = c.get(1) {
+            return Meta::get_custom_meta(&state.meta.metadata, key.as_str().to_string());
+        }
+
+        if let (Some(name), Some(default_text)) = (c.get(2), c.get(3)) {
+            return match state.vars.get(name.as_str()) {
+                Some(var) => replace_text(&var.to_string(), state),
+                None => {
+                    state.vars.insert(name.as_str().to_string(), default_text.as_str().to_string());
+
+                    replace_text(default_text.as_str(), state)
                 }
-            }).to_string()
-        },
-
-        text if text.contains("$title") => text.replace("$title", &state.meta.title),
-        text if text.contains("$artists") => text.replace("$artists", &state.meta.artists.join(", ")),
-        text if text.contains("$album") => text.replace("$album", &state.meta.album),
-        text if text.contains("$status_icon") => text.replace("$status_icon", &state.meta.status_icon.to_string()),
-        text if text.contains("$position") => text.replace("$position", &format_duration(state.meta.position)),
-        text if text.contains("$remaining-length") => text.replace("$remaining-length", &format_remaining(state.meta.position, state.meta.length)),
-        text if text.contains("$length") => text.replace("$length", &format_duration(state.meta.length)),
-
-        _ => text.to_string()
+            };
+        }
+
+        variable(&c[4], state)
+            .map(|value| value.to_text())
+            .unwrap_or_default()
+    }).to_string()
+}
+
+/// Value of a variable, named with or without its `$`. Besides the built-in ones, variables declared with var() are looked up.
+fn variable(name: &str, state: &mut FumState) -> Option<Value> {
+    let name = name.trim_start_matches('$');
+
+    let value = match name {
+        "title"            => Value::Text(state.meta.title.to_string()),
+        "artists"          => Value::List(state.meta.artists.clone()),
+        "player"           => Value::Text(state.meta.player.to_string()),
+        "album"            => Value::Text(state.meta.album.to_string()),
+        "status"           => Value::Text(format!("{:?}", state.meta.status)),
+        "status_icon"      => Value::Text(state.icons.status(&state.meta.status).to_string()),
+        "volume"           => Value::Text(format_volume(state.meta.volume)),
+        "position"         => Value::Text(format_duration(state.position())),
+        "remaining-length" => Value::Text(format_remaining(state.position(), state.meta.length)),
+        "length"           => Value::Text(format_duration(state.meta.length)),
+        "layout"           => Value::Text(state.layout.to_string()),
+        "album_artists"    => Value::List(state.meta.album_artists.clone()),
+        "track_number"     => Value::Text(optional(state.meta.track_number)),
+        "disc_number"      => Value::Text(optional(state.meta.disc_number)),
+        "genre"            => Value::List(state.meta.genres.clone()),
+        "year"             => Value::Text(optional(state.meta.year)),
+        "user_rating"      => Value::Text(optional(state.meta.user_rating)),
+        "use_count"        => Value::Text(optional(state.meta.use_count)),
+        "shuffle"          => Value::Text(match state.meta.shuffle {
+            Some(true)  => "On".to_string(),
+            Some(false) => "Off".to_string(),
+            None        => "--".to_string(),
+        }),
+        "loop_status"      => Value::Text(state.meta.loop_status.map(|status| format!("{status:?}")).unwrap_or("--".to_string())),
+        "rate"             => Value::Text(format_rate(state.meta.rate)),
+        "shuffle_icon"     => Value::Text(state.icons.shuffle(state.meta.shuffle).to_string()),
+        "loop_icon"        => Value::Text(state.icons.loop_status(state.meta.loop_status).to_string()),
+        "prev_icon"        => Value::Text(state.icons.prev.to_string()),
+        "next_icon"        => Value::Text(state.icons.next.to_string()),
+        name => {
+            let var = state.vars.get(&format!("${name}"))?.to_string();
+            Value::Text(replace_text(&var, state))
+        }
+    };
+
+    Some(value)
+}
+
+/// Text of metadata the player might not have, empty when it doesn't.
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Same as replace_text, but also parses inline markup such as `<b>$title</b> <fg=gray>by</fg> $artists`,
+/// splitting the text into spans styled by the tags they are in. Tags that aren't known are kept as text,
+/// and tags coming from variables are never parsed.
+pub fn replace_markup(text: &str, state: &mut FumState) -> Line<'static> {
+    let mut styled = StyledText { state, spans: Vec::new(), tags: Vec::new() };
+
+    template::render(text, &mut styled);
+
+    Line::from(styled.spans)
+}
+
+fn push_markup<'a>(spans: &mut Vec<Span<'static>>, tags: &mut Vec<(&'a str, Style)>, text: &'a str, state: &mut FumState) {
+    let mut last = 0;
+
+    for captures in MARKUP_TAG_RE.captures_iter(text) {
+        let tag = captures.get(0).unwrap();
+        let name = captures.get(2).unwrap().as_str();
+        let value = captures.get(3).map(|value| value.as_str());
+        let opening = captures[1].is_empty();
+
+        // Opening tags push their style, closing ones drop everything opened since the matching tag
+        let style = match opening {
+            true => tag_style(name, value),
+            false => None,
+        };
+
+        let matching = match (opening, value) {
+            (false, None) => tags.iter().rposition(|(open, _)| *open == name),
+            _ => None,
+        };
+
+        if style.is_none() && matching.is_none() {
+            continue;
+        }
+
+        push_span(spans, replace_variables(&text[last..tag.start()], state), tags);
+        last = tag.end();
+
+        match (style, matching) {
+            (Some(style), _) => tags.push((name, style)),
+            (_, Some(index)) => tags.truncate(index),
+            _ => {}
+        }
+    }
+
+    push_span(spans, replace_variables(&text[last..], state), tags);
+}
+
+fn push_span(spans: &mut Vec<Span<'static>>, text: String, tags: &[(&str, Style)]) {
+    if text.is_empty() {
+        return;
+    }
+
+    let style = tags.iter().fold(Style::new(), |style, (_, tag)| style.patch(*tag));
+
+    spans.push(Span::styled(text, style));
+}
+
+fn tag_style(name: &str, value: Option<&str>) -> Option<Style> {
+    match (name, value) {
+        ("b", None)           => Some(Style::new().bold()),
+        ("i", None)           => Some(Style::new().italic()),
+        ("u", None)           => Some(Style::new().underlined()),
+        ("dim", None)         => Some(Style::new().dim()),
+        ("rev", None)         => Some(Style::new().reversed()),
+        ("fg", Some(color))   => Color::from_str(color.trim()).ok().map(|color| Style::new().fg(color)),
+        ("bg", Some(color))   => Color::from_str(color.trim()).ok().map(|color| Style::new().bg(color)),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state() -> FumState {
+        FumState::new(Meta {
+            title: "Song".to_string(),
+            artists: vec!["First".to_string(), "Second".to_string()],
+            ..Meta::default()
+        })
+    }
+
+    #[test]
+    fn test_replace_every_variable() {
+        let mut state = state();
+
+        assert_eq!(replace_text("$title - $artists", &mut state), "Song - First, Second");
+        assert_eq!(replace_text("$status_icon $status", &mut state), format!("{} Stopped", state.icons.stop));
+        assert_eq!(replace_text("$position / $remaining-length / $length", &mut state), "0:00 / -0:00 / 0:00");
+        assert_eq!(replace_text("get_meta(xesam:title) $title", &mut state), "!NotFound Song");
+    }
+
+    #[test]
+    fn test_replace_optional_metadata() {
+        let mut state = state();
+
+        assert_eq!(replace_text("[$track_number] [$year] $shuffle $loop_status $rate", &mut state), "[] [] -- -- --");
+        assert_eq!(replace_text("[$shuffle_icon$loop_icon]", &mut state), "[]");
+
+        state.meta.track_number = Some(3);
+        state.meta.year = Some(2007);
+        state.meta.genres = vec!["Rock".to_string(), "Pop".to_string()];
+        state.meta.shuffle = Some(true);
+        state.meta.loop_status = Some(mpris::LoopStatus::Playlist);
+        state.meta.rate = Some(1.25);
+
+        assert_eq!(replace_text("[$track_number] [$year] $genre $shuffle $loop_status $rate", &mut state), "[3] [2007] Rock, Pop On Playlist 1.25x");
+        assert_eq!(replace_text("{if track_number}#$track_number {end}$title", &mut state), "#3 Song");
+        assert_eq!(replace_text("$shuffle_icon $loop_icon", &mut state), format!("{} {}", state.icons.shuffle_on, state.icons.loop_playlist));
+    }
+
+    #[test]
+    fn test_replace_vars_and_expressions() {
+        let mut state = state();
+
+        assert_eq!(replace_text("var($mode, $title) {$mode|upper}", &mut state), "Song SONG");
+        assert_eq!(replace_text("{title|upper} by $artists", &mut state), "SONG by First, Second");
+    }
+
+    #[test]
+    fn test_replace_args_keeps_values_whole() {
+        let mut state = state();
+        state.meta.title = "--delete x".to_string();
+
+        assert_eq!(
+            replace_args("notify-send $title {artists|join(' & ')} --urgency=low", &mut state),
+            vec!["notify-send", "--delete x", "First & Second", "--urgency=low"]
+        );
+    }
+
+    #[test]
+    fn test_markup_is_not_parsed_from_variables() {
+        let mut state = state();
+        state.meta.title = "<b>Song</b>".to_string();
+
+        let line = replace_markup("<i>$title</i> {title}", &mut state);
+
+        assert_eq!(line.to_string(), "<b>Song</b> <b>Song</b>");
+        assert_eq!(line.spans[0], Span::styled("<b>Song</b>", Style::new().italic()));
     }
 }
@@ -1,25 +1,30 @@
 use std::collections::HashMap;
 
+use crossterm::event::MouseEventKind;
 use ratatui::{
     layout::{Constraint, Layout, Position, Rect},
     style::Stylize,
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
-use crate::{action::Action, config::Config, state::FumState, utils};
+use crate::{
+    action::{Action, Binding, MouseBindings},
+    config::{Align, Profile},
+    state::FumState,
+    utils,
+    widget::Slider,
+};
 
-#[derive(Debug)]
-pub struct Ui<'a> {
-    config: &'a Config,
-}
+#[derive(Debug, Default)]
+pub struct Ui;
 
-impl<'a> Ui<'a> {
-    pub fn new(config: &'a Config) -> Self {
-        Self { config }
+impl Ui {
+    pub fn new() -> Self {
+        Self
     }
 
-    pub fn click(
+    pub fn click<'a>(
         &self,
         x: u16,
         y: u16,
@@ -34,20 +39,75 @@ impl<'a> Ui<'a> {
         None
     }
 
-    pub fn draw(&mut self, frame: &mut Frame<'_>, state: &mut FumState) {
-        let main_area = utils::align::get_align(
-            frame,
-            &self.config.align,
-            self.config.width,
-            self.config.height,
-        );
+    pub fn slider<'a>(
+        &self,
+        x: u16,
+        y: u16,
+        sliders: &'a HashMap<String, (Rect, Slider)>,
+    ) -> Option<&'a (Rect, Slider)> {
+        sliders
+            .values()
+            .find(|(rect, _)| rect.contains(Position::new(x, y)))
+    }
+
+    pub fn mouse_binding<'a>(
+        &self,
+        x: u16,
+        y: u16,
+        kind: MouseEventKind,
+        mouse_areas: &'a [(Rect, MouseBindings)],
+    ) -> Option<&'a Binding> {
+        mouse_areas
+            .iter()
+            .rev()
+            .filter(|(rect, _)| rect.contains(Position::new(x, y)))
+            .find_map(|(_, mouse)| mouse.get(kind))
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame<'_>, profile: &Profile, state: &mut FumState) {
+        // Scrolling labels schedule the next frame themselves
+        state.next_frame = None;
+        state
+            .marquees
+            .values_mut()
+            .for_each(|marquee| marquee.drawn = false);
+
+        self.draw_layout(frame, profile, state);
+
+        state.marquees.retain(|_, marquee| marquee.drawn);
+
+        // Keeps showing the last config error until the config is fixed
+        if let Some(error) = &state.config_error {
+            let area = utils::align::get_align(frame, profile.align, profile.width, profile.height)
+                .intersection(frame.area());
+
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(error.as_str())
+                    .wrap(Wrap::default())
+                    .red()
+                    .block(Block::new().borders(Borders::ALL).title("Config error")),
+                area,
+            );
+        }
+    }
+
+    fn draw_layout(&mut self, frame: &mut Frame<'_>, profile: &Profile, state: &mut FumState) {
+        // Sliders and mouse areas register themselves on every render
+        state.sliders.clear();
+        state.mouse_areas.clear();
+
+        let main_area =
+            utils::align::get_align(frame, profile.align, profile.width, profile.height);
+
+        // Terminal window is too small, a filling layout takes whatever there is
+        let too_small = frame.area().width < profile.width || frame.area().height < profile.height;
 
-        // Terminal window is too small
-        if &frame.area().width < &self.config.width || &frame.area().height < &self.config.height {
+        if too_small && !matches!(profile.align, Align::Fill) {
             frame.render_widget(
                 Paragraph::new(format!(
                     "Terminal window is too small. Must have atleast ({}x{}).",
-                    &self.config.width, &self.config.height
+                    &profile.width, &profile.height
                 ))
                 .centered()
                 .wrap(Wrap::default())
@@ -59,15 +119,15 @@ impl<'a> Ui<'a> {
         }
 
         // Sets the state parents state
-        state.parent_direction = self.config.direction.to_owned();
-        state.parent_bg = self.config.bg;
-        state.parent_fg = self.config.fg;
+        state.parent_direction = profile.direction.to_owned();
+        state.parent_bg = profile.bg;
+        state.parent_fg = profile.fg;
 
         let areas = Layout::default()
-            .direction(self.config.direction.to_dir())
-            .flex(self.config.flex.to_flex())
+            .direction(profile.direction.to_dir())
+            .flex(profile.flex.to_flex())
             .constraints(
-                self.config
+                profile
                     .layout
                     .iter()
                     .map(|child| child.get_size(state))
@@ -81,7 +141,7 @@ impl<'a> Ui<'a> {
             main_area,
         );
 
-        for (i, widget) in self.config.layout.iter().enumerate() {
+        for (i, widget) in profile.layout.iter().enumerate() {
             if let Some(area) = areas.get(i) {
                 frame.render_stateful_widget(widget, *area, state);
             }
@@ -108,6 +108,7 @@ pub fn get_align(frame: &mut Frame<'_>, align: &Align, width: u16, height: u16)
         Align::TopLeft          => top_left(frame, width, height),
         Align::TopRight         => top_right(frame, width, height),
         Align::BottomLeft       => bottom_left(frame, width, height),
-        Align::BottomRight      => bottom_right(frame, width, height)
+        Align::BottomRight      => bottom_right(frame, width, height),
+        Align::Fill             => frame.area()
     }
 }
@@ -1,6 +1,11 @@
-use std::time::Duration;
+use std::{ffi::OsStr, process::{Command, Stdio}, time::Duration};
+use ratatui::{style::Style, text::{Line, Span}};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 use uuid::Uuid;
 
+use crate::widget::TruncatePosition;
+
 #[macro_export]
 macro_rules! get_size {
     ($orientation:expr, $size:expr, $area:expr) => {{
@@ -34,13 +39,149 @@ pub fn generate_btn_id() -> String {
     Uuid::new_v4().to_string()
 }
 
-pub fn truncate(string: &str, width: usize) -> String {
-    if string.chars().count() <= width {
-        string.to_string()
-    } else {
-        // minus 3 since the dots (...)
-        let truncated: String = string.chars().take(width - 3).collect();
-        format!("{}...", truncated)
+/// Spawns a program with its arguments in the background, ignoring its output and result.
+pub fn spawn<S: AsRef<OsStr>>(args: &[S]) {
+    if let Some((program, args)) = args.split_first() {
+        let _ = Command::new(program)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+}
+
+/// Number of cells a text takes, by the width of its grapheme clusters.
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(|grapheme| grapheme.width()).sum()
+}
+
+/// Number of cells a line takes, see display_width.
+pub fn line_width(line: &Line) -> usize {
+    line.spans.iter().map(|span| display_width(&span.content)).sum()
+}
+
+/// Cuts a text down to `width` cells, putting the ellipsis where it was cut.
+pub fn truncate(string: &str, width: usize, ellipsis: &str, position: &TruncatePosition) -> String {
+    truncate_line(Line::from(string.to_string()), width, ellipsis, position).to_string()
+}
+
+/// Same as truncate, but keeps the style of every span. The ellipsis takes the style of the text it replaces.
+pub fn truncate_line(line: Line<'static>, width: usize, ellipsis: &str, position: &TruncatePosition) -> Line<'static> {
+    let graphemes = styled_graphemes(&line);
+
+    if graphemes.iter().map(|(_, width, _)| width).sum::<usize>() <= width {
+        return line;
+    }
+
+    // An ellipsis wider than the text is cut too
+    let ellipsis_length = fitting(ellipsis.graphemes(true).map(|grapheme| grapheme.width()), width);
+    let ellipsis: String = ellipsis.graphemes(true).take(ellipsis_length).collect();
+    let available = width - display_width(&ellipsis);
+
+    let head_budget = match position {
+        TruncatePosition::Start => 0,
+        TruncatePosition::Middle => available - available / 2,
+        TruncatePosition::End => available,
+    };
+
+    let head = fitting(graphemes.iter().map(|(_, width, _)| *width), head_budget);
+    let head_width: usize = graphemes[..head].iter().map(|(_, width, _)| width).sum();
+
+    // Whatever the head couldn't use goes to the tail, e.g. when a wide grapheme didn't fit
+    let tail_budget = match position {
+        TruncatePosition::End => 0,
+        _ => available - head_width,
+    };
+
+    let tail = fitting(graphemes[head..].iter().rev().map(|(_, width, _)| *width), tail_budget);
+    let tail_start = graphemes.len() - tail;
+
+    let mut spans = Vec::new();
+
+    for (grapheme, _, style) in &graphemes[..head] {
+        push_styled(&mut spans, grapheme, *style);
+    }
+
+    if let Some((_, _, style)) = graphemes.get(head) {
+        push_styled(&mut spans, &ellipsis, *style);
+    }
+
+    for (grapheme, _, style) in &graphemes[tail_start..] {
+        push_styled(&mut spans, grapheme, *style);
+    }
+
+    Line { spans, ..line }
+}
+
+/// The `width` cells of a line scrolled by `offset` cells, repeating after the separator.
+/// Wide graphemes cut by either edge are left blank.
+pub fn scroll_line(line: &Line<'static>, separator: &str, offset: usize, width: usize) -> Line<'static> {
+    let separator = Line::from(separator.to_string());
+
+    let graphemes: Vec<(&str, usize, Style)> = styled_graphemes(line)
+        .into_iter()
+        .chain(styled_graphemes(&separator))
+        .filter(|(_, width, _)| *width > 0)
+        .collect();
+
+    let cycle: usize = graphemes.iter().map(|(_, width, _)| width).sum();
+
+    if cycle == 0 {
+        return Line::default();
+    }
+
+    let mut skip = offset % cycle;
+    let mut left = width;
+    let mut spans = Vec::new();
+
+    for (grapheme, grapheme_width, style) in graphemes.iter().cycle() {
+        if left == 0 {
+            break;
+        }
+
+        let content = match skip {
+            0 if *grapheme_width <= left => grapheme.to_string(),
+            0 => " ".repeat(left),
+            _ if *grapheme_width <= skip => {
+                skip -= grapheme_width;
+                continue;
+            },
+            _ => " ".repeat((grapheme_width - skip).min(left)),
+        };
+
+        skip = 0;
+        left -= display_width(&content);
+
+        push_styled(&mut spans, &content, *style);
+    }
+
+    Line::from(spans)
+}
+
+/// Every grapheme cluster of a line with its width and style.
+fn styled_graphemes<'a>(line: &'a Line) -> Vec<(&'a str, usize, Style)> {
+    line.spans.iter()
+        .flat_map(|span| span.content.graphemes(true).map(|grapheme| (grapheme, grapheme.width(), span.style)))
+        .collect()
+}
+
+/// How many of the widths fit in `width` cells, taken in order.
+fn fitting(widths: impl Iterator<Item = usize>, width: usize) -> usize {
+    let mut total = 0;
+
+    widths
+        .take_while(|grapheme_width| {
+            total += grapheme_width;
+            total <= width
+        })
+        .count()
+}
+
+/// Appends text to the last span if it has the same style, to a new span otherwise.
+fn push_styled(spans: &mut Vec<Span<'static>>, content: &str, style: Style) {
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.content.to_mut().push_str(content),
+        _ => spans.push(Span::styled(content.to_string(), style)),
     }
 }
 
@@ -57,12 +198,93 @@ pub fn format_duration(duration: Duration) -> String {
     }
 }
 
+pub fn format_volume(volume: Option<f64>) -> String {
+    match volume {
+        Some(volume) => format!("{}%", (volume * 100.0).round()),
+        None => "--".to_string(),
+    }
+}
+
+pub fn format_rate(rate: Option<f64>) -> String {
+    match rate {
+        Some(rate) => format!("{}x", (rate * 100.0).round() / 100.0),
+        None => "--".to_string(),
+    }
+}
+
 pub fn format_remaining(current: Duration, total: Duration) -> String {
     if total > current {
         let remaining = total - current;
         format!("-{}", format_duration(remaining))
     } else {
-        format!("-0:00")
+        "-0:00".to_string()
     }
 }
 
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ratatui::style::Stylize;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("title"), 5);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("😀!"), 3);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(display_width("󰒮"), 1);
+    }
+
+    #[test]
+    fn test_truncate_fitting_text() {
+        assert_eq!(truncate("title", 5, "...", &TruncatePosition::End), "title");
+        assert_eq!(truncate("日本語", 6, "...", &TruncatePosition::End), "日本語");
+    }
+
+    #[test]
+    fn test_truncate_positions() {
+        assert_eq!(truncate("abcdefghij", 7, "...", &TruncatePosition::End), "abcd...");
+        assert_eq!(truncate("abcdefghij", 7, "...", &TruncatePosition::Middle), "ab...ij");
+        assert_eq!(truncate("abcdefghij", 7, "...", &TruncatePosition::Start), "...ghij");
+    }
+
+    #[test]
+    fn test_truncate_wide_graphemes() {
+        assert_eq!(truncate("日本語のタイトル", 7, "...", &TruncatePosition::End), "日本...");
+
+        // Half of a wide grapheme never fits
+        assert_eq!(truncate("日本語のタイトル", 6, "...", &TruncatePosition::End), "日...");
+        assert_eq!(truncate("日本語のタイトル", 6, "...", &TruncatePosition::Start), "...ル");
+        assert_eq!(truncate("日本語のタイトル", 9, "…", &TruncatePosition::Middle), "日本…トル");
+
+        assert_eq!(truncate("😀😀😀😀", 5, "…", &TruncatePosition::End), "😀😀…");
+        assert_eq!(truncate("cafe\u{301} noir", 5, "…", &TruncatePosition::End), "cafe\u{301}…");
+    }
+
+    #[test]
+    fn test_truncate_narrow_width() {
+        assert_eq!(truncate("abcdef", 2, "...", &TruncatePosition::End), "..");
+        assert_eq!(truncate("abcdef", 0, "...", &TruncatePosition::End), "");
+        assert_eq!(truncate("日本語", 1, "…", &TruncatePosition::Middle), "…");
+    }
+
+    #[test]
+    fn test_truncate_line_keeps_styles() {
+        let line = Line::from(vec!["日本".bold(), "語です".into()]);
+        let truncated = truncate_line(line, 7, "…", &TruncatePosition::End);
+
+        assert_eq!(truncated.spans, vec!["日本".bold(), "語…".into()]);
+    }
+
+    #[test]
+    fn test_scroll_line_wide_graphemes() {
+        let line = Line::from("日本ab");
+
+        assert_eq!(scroll_line(&line, " ", 0, 4).to_string(), "日本");
+        assert_eq!(scroll_line(&line, " ", 1, 4).to_string(), " 本a");
+        assert_eq!(scroll_line(&line, " ", 5, 4).to_string(), "b 日");
+        assert_eq!(scroll_line(&line, " ", 6, 4).to_string(), " 日 ");
+        assert_eq!(scroll_line(&line, " ", 7, 4).to_string(), "日本");
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::fum::FumResult;
+
+/// Watches the config file and the files it includes, sends on every change to them. The watcher stops
+/// once it's dropped.
+pub fn watch(paths: &[&Path], sender: Sender<()>) -> FumResult<RecommendedWatcher> {
+    let mut dirs: Vec<&Path> = Vec::new();
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for path in paths {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::other("Config path has no file name"))?;
+
+        // Events carry the path of the watched directory joined with the file name
+        files.push(dir.join(file_name));
+
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+
+        let changed = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event.paths.iter().any(|path| files.contains(path));
+
+        if changed {
+            let _ = sender.send(());
+        }
+    })
+    .map_err(|err| format!("Failed to watch config: {err}"))?;
+
+    // Watch the directories since editors usually replace the file rather than write to it. Only the
+    // config's own one has to be watchable, a missing include is reported as a config error anyway
+    for (i, dir) in dirs.into_iter().enumerate() {
+        let watched = watcher.watch(dir, RecursiveMode::NonRecursive);
+
+        if i == 0 {
+            watched.map_err(|err| format!("Failed to watch config: {err}"))?;
+        }
+    }
+
+    Ok(watcher)
+}
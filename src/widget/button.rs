@@ -1,16 +1,18 @@
 use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, widgets::{Block, Paragraph, Widget}};
 
-use crate::{get_color, state::FumState, text::replace_text};
+use crate::{get_color, state::FumState, text::replace_markup};
 
 use super::FumWidget;
 
 pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumState) {
-    if let FumWidget::Button { id, text, action, exec, bg, fg } = widget {
-        let text = replace_text(text, state).to_string();
+    if let FumWidget::Button { id, text, action, exec, bg, fg, style, mouse, .. } = widget {
+        let text = replace_markup(text, state);
+
+        state.register_mouse(area, mouse);
 
         state.buttons.insert(
             id.to_string(),
-            (area.clone(), action.to_owned(), exec.to_owned())
+            (area, action.to_owned(), exec.to_owned())
         );
 
         let (bg, fg) = get_color!(bg, fg, &state.parent_bg, &state.parent_fg);
@@ -22,6 +24,7 @@ pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumS
 
         Paragraph::new(text)
             .fg(*fg)
+            .add_modifier(style.to_modifier())
             .render(area, buf);
     }
 }
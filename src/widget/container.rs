@@ -1,30 +1,55 @@
-use ratatui::{buffer::Buffer, layout::{Constraint, Layout, Rect}, style::Stylize, widgets::{Block, StatefulWidget, Widget}};
+use ratatui::{buffer::Buffer, layout::{Constraint, Layout, Rect}, style::{Style, Stylize}, widgets::{Block, Borders, Padding, StatefulWidget, Widget}};
 
-use crate::{get_color, state::FumState};
+use crate::{get_color, state::FumState, text::replace_markup};
 
-use super::FumWidget;
+use super::{Direction, FumWidget};
 
 pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumState) {
-    if let FumWidget::Container { width, height, direction, children, flex, bg, fg } = widget {
-        let area = Rect::new(
-            area.x,
-            area.y,
-            width.unwrap_or(area.width),
-            height.unwrap_or(area.height)
-        );
+    if let FumWidget::Container { width, height, direction, children, flex, bg, fg, border, border_fg, title, title_align, padding, margin, style, mouse } = widget {
+        // The parent's layout already sized the main axis, only the cross one is left
+        let area = match state.parent_direction {
+            Direction::Horizontal => Rect { height: height.map(|h| h.resolve(area.height)).unwrap_or(area.height), ..area },
+            Direction::Vertical   => Rect { width: width.map(|w| w.resolve(area.width)).unwrap_or(area.width), ..area },
+        };
+
+        // The margin is left to the parent
+        let area = margin.shrink(area);
+
+        state.register_mouse(area, mouse);
 
         // Render this container block with defined or parent's bg / fg
         let (bg, fg) = get_color!(bg, fg, &state.parent_bg, &state.parent_fg);
+        let (bg, fg) = (*bg, *fg);
+
+        let mut block = Block::new()
+            .bg(bg)
+            .fg(fg)
+            .add_modifier(style.to_modifier())
+            .padding(Padding::new(padding.left, padding.right, padding.top, padding.bottom));
+
+        if let Some(border_type) = border.to_border_type() {
+            block = block
+                .borders(Borders::ALL)
+                .border_type(border_type)
+                .border_style(Style::new().fg(border_fg.unwrap_or(fg)));
+        }
 
-        Block::new()
-            .bg(*bg)
-            .fg(*fg)
-            .render(area, buf);
+        if let Some(title) = title {
+            block = block
+                .title(replace_markup(title, state))
+                .title_alignment(title_align.to_alignment());
+        }
+
+        // Children go inside of the border and padding
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // Sets the state parents state, restored for the siblings once the children are rendered
+        let parent = (state.parent_direction.to_owned(), state.parent_bg, state.parent_fg);
 
-        // Sets the state parents state
         state.parent_direction = direction.to_owned();
-        state.parent_bg = *bg;
-        state.parent_fg = *fg;
+        state.parent_bg = bg;
+        state.parent_fg = fg;
 
         let areas = Layout::default()
             .direction(direction.to_dir())
@@ -35,12 +60,19 @@ pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumS
                     .map(|child| child.get_size(state))
                     .collect::<Vec<Constraint>>()
             )
-            .split(area);
+            .split(inner);
 
         for (i, child) in children.iter().enumerate() {
             if let Some(area) = areas.get(i) {
                 child.render(*area, buf, state);
             }
+
+            // Nested containers set their own direction and colors
+            state.parent_direction = direction.to_owned();
+            state.parent_bg = bg;
+            state.parent_fg = fg;
         }
+
+        (state.parent_direction, state.parent_bg, state.parent_fg) = parent;
     }
 }
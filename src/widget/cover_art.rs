@@ -1,4 +1,4 @@
-use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, widgets::{Block, StatefulWidget, Widget}};
+use ratatui::{buffer::Buffer, layout::{Constraint, Flex, Layout, Rect}, style::Stylize, widgets::{Block, Paragraph, StatefulWidget, Widget}};
 use ratatui_image::StatefulImage;
 
 use crate::{get_color, state::FumState};
@@ -6,8 +6,10 @@ use crate::{get_color, state::FumState};
 use super::FumWidget;
 
 pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumState) {
-    if let FumWidget::CoverArt { resize, bg, fg, .. } = widget {
-        let (bg, _) = get_color!(bg, fg, &state.parent_bg, &state.parent_fg);
+    if let FumWidget::CoverArt { resize, bg, fg, style, mouse, .. } = widget {
+        state.register_mouse(area, mouse);
+
+        let (bg, fg) = get_color!(bg, fg, &state.parent_bg, &state.parent_fg);
 
         // Render bg
         Block::new()
@@ -21,6 +23,17 @@ pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumS
                 buf,
                 &mut cover_art.image
             );
+        } else if state.meta.cover_art_loading {
+            // Placeholder while the art is being loaded
+            let [placeholder_area] = Layout::vertical([Constraint::Length(1)])
+                .flex(Flex::Center)
+                .areas(area);
+
+            Paragraph::new(state.icons.cover_art.as_str())
+                .centered()
+                .fg(*fg)
+                .add_modifier(style.to_modifier())
+                .render(placeholder_area, buf);
         }
     }
 }
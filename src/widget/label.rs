@@ -1,14 +1,28 @@
+use std::time::Duration;
+
 use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, widgets::{Block, Paragraph, Widget}};
 
-use crate::{get_color, state::FumState, text::replace_text, utils};
+use crate::{get_color, state::FumState, text::replace_markup, utils};
 
 use super::{FumWidget, LabelAlignment};
 
 pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumState) {
-    if let FumWidget::Label { text, truncate, align, bg, fg } = widget {
-        let text = match truncate {
-            true => utils::etc::truncate(&replace_text(text, state), area.width.into()),
-            false => replace_text(text, state)
+    if let FumWidget::Label { id, text, truncate, truncate_position, ellipsis, scroll, scroll_speed, scroll_pause, scroll_separator, align, bg, fg, style, mouse, .. } = widget {
+        state.register_mouse(area, mouse);
+
+        let text = replace_markup(text, state);
+        let width: usize = area.width.into();
+
+        let text = match (scroll, truncate) {
+            (true, _) if utils::etc::line_width(&text) > width => {
+                let text_width = utils::etc::line_width(&text);
+                let cycle = text_width + utils::etc::display_width(scroll_separator);
+                let offset = state.marquee_offset(id, cycle, text_width - width, *scroll_speed, Duration::from_millis(*scroll_pause));
+
+                utils::etc::scroll_line(&text, scroll_separator, offset, width)
+            },
+            (_, true) => utils::etc::truncate_line(text, width, ellipsis, truncate_position),
+            (_, false) => text
         };
 
         let (bg, fg) = get_color!(bg, fg, &state.parent_bg, &state.parent_fg);
@@ -24,6 +38,8 @@ pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumS
             .bg(*bg)
             .render(area, buf);
 
-        widget.render(area, buf);
+        widget
+            .add_modifier(style.to_modifier())
+            .render(area, buf);
     }
 }
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod widget;
 mod container;
 mod cover_art;
@@ -5,5 +6,7 @@ mod label;
 mod button;
 mod progress;
 mod empty;
+mod volume;
+mod player_list;
 
 pub use widget::*;
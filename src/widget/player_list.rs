@@ -0,0 +1,61 @@
+use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, widgets::{Block, Paragraph, Widget}};
+
+use crate::{action::Action, get_color, state::FumState, utils};
+
+use super::{FumWidget, TruncatePosition};
+
+pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumState) {
+    if let FumWidget::PlayerList { id, bg, fg, active_bg, active_fg, style, .. } = widget {
+        // Without highlight colors the active row is rendered reversed
+        let reversed = active_bg.is_none() && active_fg.is_none();
+
+        let (bg, fg) = get_color!(bg, fg, &state.parent_bg, &state.parent_fg);
+        let (active_bg, active_fg) = get_color!(active_bg, active_fg, bg, fg);
+
+        // Render bg
+        Block::new()
+            .bg(*bg)
+            .render(area, buf);
+
+        // Forget the rows of the previous render, players may have come and gone since
+        let prefix = format!("{id}:");
+        state.buttons.retain(|button_id, _| !button_id.starts_with(&prefix));
+
+        for (i, info) in state.players.iter().enumerate() {
+            if i as u16 >= area.height {
+                break;
+            }
+
+            let row = Rect::new(area.x, area.y + i as u16, area.width, 1);
+            let is_active = state.active_player.as_ref() == Some(&info.bus_name);
+
+            let text = match info.title.is_empty() {
+                true => format!("{} {}", state.icons.status(&info.status), info.identity),
+                false => format!("{} {}: {}", state.icons.status(&info.status), info.identity, info.title),
+            };
+
+            let (row_bg, row_fg) = match is_active {
+                true => (active_bg, active_fg),
+                false => (bg, fg),
+            };
+
+            Block::new()
+                .bg(*row_bg)
+                .render(row, buf);
+
+            let paragraph = Paragraph::new(utils::etc::truncate(&text, row.width.into(), "...", &TruncatePosition::End))
+                .fg(*row_fg)
+                .add_modifier(style.to_modifier());
+
+            match is_active && reversed {
+                true => paragraph.reversed().render(row, buf),
+                false => paragraph.render(row, buf),
+            }
+
+            state.buttons.insert(
+                format!("{prefix}{}", info.bus_name),
+                (row, Some(Action::SelectPlayer(info.bus_name.to_string())), None)
+            );
+        }
+    }
+}
@@ -1,59 +1,77 @@
-use ratatui::{buffer::Buffer, layout::{Constraint, Layout, Rect}, style::Stylize, text::Text, widgets::{Block, Widget}};
+use ratatui::{buffer::Buffer, layout::{Constraint, Layout, Rect}, style::{Modifier, Stylize}, text::Text, widgets::{Block, Widget}};
 
 use crate::{get_color, state::FumState};
 
-use super::FumWidget;
+use super::{FumWidget, ProgressOption, Slider};
 
 pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumState) {
-    if let FumWidget::Progress { progress: prog_opt, empty: empt_opt, .. } = widget {
-        let (prog_bg, prog_fg) = get_color!(&prog_opt.bg, &prog_opt.fg, &state.parent_bg, &state.parent_fg);
-        let (empt_bg, empt_fg) = get_color!(&empt_opt.bg, &empt_opt.fg, &state.parent_bg, &state.parent_fg);
-
-        let progress_char = prog_opt.char.to_string();
-        let empty_char = empt_opt.char.to_string();
-
-        if state.meta.length.as_secs() > 0 {
-            let ratio = state.meta.position.as_secs() as f64 / state.meta.length.as_secs() as f64;
-
-            let filled = (ratio * area.width as f64).round();
-            let empty = area.width.saturating_sub(filled as u16);
-
-            let progress_bar = progress_char.repeat(filled as usize);
-            let empty_bar = empty_char.repeat(empty.into());
-
-            let [progress_area, empty_area] = Layout::horizontal([
-                Constraint::Length(filled as u16),
-                Constraint::Length(empty as u16),
-            ]).areas(area);
-
-            // Render progress bg
-            Block::new()
-                .bg(*prog_bg)
-                .render(area, buf);
-
-            // Render progress
-            Text::from(progress_bar)
-                .fg(*prog_fg)
-                .render(progress_area, buf);
-
-            // Render empty bg
-            Block::new()
-                .bg(*empt_bg)
-                .render(area, buf);
-
-            // Render empty
-            Text::from(empty_bar)
-                .fg(*empt_fg)
-                .render(empty_area, buf);
-        } else {
-            // Render empty bg
-            Block::new()
-                .bg(*empt_bg)
-                .render(area, buf);
-
-            Text::from(empty_char.repeat(area.width.into()))
-                .fg(*empt_fg)
-                .render(area, buf);
-        }
+    if let FumWidget::Progress { id, progress: prog_opt, empty: empt_opt, style, mouse, .. } = widget {
+        state.sliders.insert(
+            id.to_string(),
+            (area, Slider::Progress)
+        );
+
+        state.register_mouse(area, mouse);
+
+        let ratio = match state.meta.length.as_secs() > 0 {
+            true => Some(state.position().as_secs() as f64 / state.meta.length.as_secs() as f64),
+            false => None,
+        };
+
+        render_bar(ratio, prog_opt, empt_opt, style.to_modifier(), area, buf, state);
+    }
+}
+
+/// Renders a bar filled up to `ratio`, or a completely empty one if there's no ratio.
+pub fn render_bar(ratio: Option<f64>, prog_opt: &ProgressOption, empt_opt: &ProgressOption, modifier: Modifier, area: Rect, buf: &mut Buffer, state: &FumState) {
+    let (prog_bg, prog_fg) = get_color!(&prog_opt.bg, &prog_opt.fg, &state.parent_bg, &state.parent_fg);
+    let (empt_bg, empt_fg) = get_color!(&empt_opt.bg, &empt_opt.fg, &state.parent_bg, &state.parent_fg);
+
+    let progress_char = prog_opt.char.unwrap_or(state.icons.progress).to_string();
+    let empty_char = empt_opt.char.unwrap_or(state.icons.progress_empty).to_string();
+
+    if let Some(ratio) = ratio {
+        let filled = (ratio.clamp(0.0, 1.0) * area.width as f64).round();
+        let empty = area.width.saturating_sub(filled as u16);
+
+        let progress_bar = progress_char.repeat(filled as usize);
+        let empty_bar = empty_char.repeat(empty.into());
+
+        let [progress_area, empty_area] = Layout::horizontal([
+            Constraint::Length(filled as u16),
+            Constraint::Length(empty),
+        ]).areas(area);
+
+        // Render progress bg
+        Block::new()
+            .bg(*prog_bg)
+            .render(area, buf);
+
+        // Render progress
+        Text::from(progress_bar)
+            .fg(*prog_fg)
+            .add_modifier(modifier)
+            .render(progress_area, buf);
+
+        // Render empty bg
+        Block::new()
+            .bg(*empt_bg)
+            .render(area, buf);
+
+        // Render empty
+        Text::from(empty_bar)
+            .fg(*empt_fg)
+            .add_modifier(modifier)
+            .render(empty_area, buf);
+    } else {
+        // Render empty bg
+        Block::new()
+            .bg(*empt_bg)
+            .render(area, buf);
+
+        Text::from(empty_char.repeat(area.width.into()))
+            .fg(*empt_fg)
+            .add_modifier(modifier)
+            .render(area, buf);
     }
 }
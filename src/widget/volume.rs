@@ -0,0 +1,18 @@
+use ratatui::{buffer::Buffer, layout::Rect};
+
+use crate::state::FumState;
+
+use super::{progress, FumWidget, Slider};
+
+pub fn render(widget: &FumWidget, area: Rect, buf: &mut Buffer, state: &mut FumState) {
+    if let FumWidget::Volume { id, progress: prog_opt, empty: empt_opt, step, style, mouse, .. } = widget {
+        state.sliders.insert(
+            id.to_string(),
+            (area, Slider::Volume { step: *step })
+        );
+
+        state.register_mouse(area, mouse);
+
+        progress::render_bar(state.meta.volume, prog_opt, empt_opt, style.to_modifier(), area, buf, state);
+    }
+}
@@ -1,31 +1,176 @@
-use crate::{action::Action, state::FumState, text::replace_text, utils::etc::generate_btn_id};
+use std::fmt;
+
+use crate::{
+    action::{Action, MouseBindings},
+    state::FumState,
+    text::replace_markup,
+    utils::etc::{generate_btn_id, line_width},
+};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
-    style::Color,
+    style::{Color, Modifier},
     widgets::StatefulWidget,
 };
-use serde::Deserialize;
+use serde::{
+    de::{self, Visitor},
+    Deserialize,
+};
 
-use super::{button, container, cover_art, empty, label, progress};
+use super::{button, container, cover_art, empty, label, player_list, progress, volume};
 
 fn default_truncate() -> bool {
     true
 }
 
-#[derive(Debug, Clone, Deserialize)]
+fn default_ellipsis() -> String {
+    "...".to_string()
+}
+
+fn default_volume_step() -> i64 {
+    5
+}
+
+fn default_scroll_speed() -> f64 {
+    4.0
+}
+
+fn default_scroll_pause() -> u64 {
+    1500
+}
+
+fn default_scroll_separator() -> String {
+    "   ".to_string()
+}
+
+/// Widgets that can be clicked or scrolled to set a value.
+#[derive(Debug, Clone)]
+pub enum Slider {
+    Progress,
+    Volume { step: i64 },
+}
+
+/// Size of a widget, either a number of cells or one of `"40%"`, `"1/3"`, `"min:10"`, `"max:30"`, `"fill:2"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    Length(u16),
+    Percentage(u16),
+    Ratio(u32, u32),
+    Min(u16),
+    Max(u16),
+    Fill(u16),
+}
+
+impl Size {
+    pub fn to_constraint(self) -> Constraint {
+        match self {
+            Self::Length(length) => Constraint::Length(length),
+            Self::Percentage(percentage) => Constraint::Percentage(percentage),
+            Self::Ratio(numerator, denominator) => Constraint::Ratio(numerator, denominator),
+            Self::Min(min) => Constraint::Min(min),
+            Self::Max(max) => Constraint::Max(max),
+            Self::Fill(weight) => Constraint::Fill(weight),
+        }
+    }
+
+    /// The size taken out of `total`, for when there is no layout to split it.
+    pub fn resolve(self, total: u16) -> u16 {
+        match self {
+            Self::Length(length) | Self::Max(length) => length.min(total),
+            Self::Percentage(percentage) => (total as u32 * percentage as u32 / 100) as u16,
+            Self::Ratio(numerator, denominator) => {
+                let size = total as u64 * numerator as u64 / denominator.max(1) as u64;
+                size.min(total as u64) as u16
+            }
+            Self::Min(_) | Self::Fill(_) => total,
+        }
+    }
+
+    /// Number of cells if the size is fixed.
+    pub fn length(self) -> Option<u16> {
+        match self {
+            Self::Length(length) => Some(length),
+            _ => None,
+        }
+    }
+
+    fn parse(size: &str) -> Option<Self> {
+        let size = size.trim();
+
+        if let Some(percentage) = size.strip_suffix('%') {
+            return percentage.trim().parse().ok().map(Self::Percentage);
+        }
+
+        if let Some((numerator, denominator)) = size.split_once('/') {
+            let numerator = numerator.trim().parse().ok()?;
+            let denominator = denominator.trim().parse().ok().filter(|d| *d != 0)?;
+
+            return Some(Self::Ratio(numerator, denominator));
+        }
+
+        match size.split_once(':') {
+            Some(("min", min)) => min.trim().parse().ok().map(Self::Min),
+            Some(("max", max)) => max.trim().parse().ok().map(Self::Max),
+            Some(("fill", weight)) => weight.trim().parse().ok().map(Self::Fill),
+            Some(_) => None,
+            None => size.parse().ok().map(Self::Length),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Size {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SizeVisitor)
+    }
+}
+
+struct SizeVisitor;
+
+impl Visitor<'_> for SizeVisitor {
+    type Value = Size;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .write_str("a number or one of \"40%\", \"1/3\", \"min:10\", \"max:30\", \"fill:2\"")
+    }
+
+    fn visit_u64<E>(self, size: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u16::try_from(size)
+            .map(Size::Length)
+            .map_err(|_| de::Error::custom(format!("Size is too big: {size}")))
+    }
+
+    fn visit_i64<E>(self, size: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u16::try_from(size)
+            .map(Size::Length)
+            .map_err(|_| de::Error::custom(format!("Invalid size: {size}")))
+    }
+
+    fn visit_str<E>(self, size: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Size::parse(size).ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(size), &self))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Direction {
     Vertical,
+    #[default]
     Horizontal,
 }
 
-impl Default for Direction {
-    fn default() -> Self {
-        Self::Horizontal
-    }
-}
-
 impl Direction {
     pub fn to_dir(&self) -> ratatui::layout::Direction {
         match self {
@@ -35,23 +180,39 @@ impl Direction {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LabelAlignment {
+    #[default]
     Left,
     Center,
     Right,
 }
 
-impl Default for LabelAlignment {
-    fn default() -> Self {
-        Self::Left
+impl LabelAlignment {
+    pub fn to_alignment(&self) -> ratatui::layout::Alignment {
+        match self {
+            Self::Left => ratatui::layout::Alignment::Left,
+            Self::Center => ratatui::layout::Alignment::Center,
+            Self::Right => ratatui::layout::Alignment::Right,
+        }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Which part of a text is cut when it doesn't fit.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TruncatePosition {
+    Start,
+    Middle,
+    #[default]
+    End,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ContainerFlex {
+    #[default]
     Start,
     Center,
     End,
@@ -61,12 +222,6 @@ pub enum ContainerFlex {
     SpaceBetween,
 }
 
-impl Default for ContainerFlex {
-    fn default() -> Self {
-        ContainerFlex::Start
-    }
-}
-
 impl ContainerFlex {
     pub fn to_flex(&self) -> ratatui::layout::Flex {
         match self {
@@ -79,20 +234,111 @@ impl ContainerFlex {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerBorder {
+    #[default]
+    None,
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+}
+
+impl ContainerBorder {
+    pub fn to_border_type(&self) -> Option<ratatui::widgets::BorderType> {
+        match self {
+            Self::None => None,
+            Self::Plain => Some(ratatui::widgets::BorderType::Plain),
+            Self::Rounded => Some(ratatui::widgets::BorderType::Rounded),
+            Self::Double => Some(ratatui::widgets::BorderType::Double),
+            Self::Thick => Some(ratatui::widgets::BorderType::Thick),
+        }
+    }
+}
+
+/// Space around each side, either one number for all of them, `[vertical, horizontal]`
+/// or `[top, right, bottom, left]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spacing {
+    pub top: u16,
+    pub right: u16,
+    pub bottom: u16,
+    pub left: u16,
+}
+
+impl Spacing {
+    pub fn horizontal(&self) -> u16 {
+        self.left.saturating_add(self.right)
+    }
+
+    pub fn vertical(&self) -> u16 {
+        self.top.saturating_add(self.bottom)
+    }
+
+    /// The area left inside of this spacing.
+    pub fn shrink(&self, area: Rect) -> Rect {
+        let x = area.x.saturating_add(self.left.min(area.width));
+        let y = area.y.saturating_add(self.top.min(area.height));
+
+        Rect {
+            x,
+            y,
+            width: area.width.saturating_sub(self.horizontal()),
+            height: area.height.saturating_sub(self.vertical()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Spacing {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawSpacing {
+            All(u16),
+            Sides(Vec<u16>),
+        }
+
+        match RawSpacing::deserialize(deserializer)? {
+            RawSpacing::All(all) => Ok(Spacing {
+                top: all,
+                right: all,
+                bottom: all,
+                left: all,
+            }),
+            RawSpacing::Sides(sides) => match sides[..] {
+                [vertical, horizontal] => Ok(Spacing {
+                    top: vertical,
+                    right: horizontal,
+                    bottom: vertical,
+                    left: horizontal,
+                }),
+                [top, right, bottom, left] => Ok(Spacing {
+                    top,
+                    right,
+                    bottom,
+                    left,
+                }),
+                _ => Err(de::Error::custom(
+                    "Spacing must be a number, [vertical, horizontal] or [top, right, bottom, left]",
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum CoverArtResize {
     Fit,
     Crop,
+    #[default]
     Scale,
 }
 
-impl Default for CoverArtResize {
-    fn default() -> Self {
-        Self::Scale
-    }
-}
-
 impl CoverArtResize {
     pub fn to_resize(&self) -> ratatui_image::Resize {
         match self {
@@ -105,9 +351,40 @@ impl CoverArtResize {
     }
 }
 
+/// Text modifiers of a widget, added on top of the ones of its parent.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct TextStyle {
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub underline: bool,
+    #[serde(default)]
+    pub dim: bool,
+    #[serde(default)]
+    pub reversed: bool,
+}
+
+impl TextStyle {
+    pub fn to_modifier(self) -> Modifier {
+        let mut modifier = Modifier::empty();
+
+        modifier.set(Modifier::BOLD, self.bold);
+        modifier.set(Modifier::ITALIC, self.italic);
+        modifier.set(Modifier::UNDERLINED, self.underline);
+        modifier.set(Modifier::DIM, self.dim);
+        modifier.set(Modifier::REVERSED, self.reversed);
+
+        modifier
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ProgressOption {
-    pub char: char,
+    /// Defaults to the progress icons.
+    #[serde(default)]
+    pub char: Option<char>,
     pub bg: Option<Color>,
     pub fg: Option<Color>,
 }
@@ -117,8 +394,8 @@ pub struct ProgressOption {
 #[serde(rename_all = "lowercase")]
 pub enum FumWidget {
     Container {
-        width: Option<u16>,
-        height: Option<u16>,
+        width: Option<Size>,
+        height: Option<Size>,
         #[serde(default = "Direction::default")]
         direction: Direction,
         children: Vec<FumWidget>,
@@ -126,43 +403,122 @@ pub enum FumWidget {
         flex: ContainerFlex,
         bg: Option<Color>,
         fg: Option<Color>,
+        #[serde(default = "ContainerBorder::default")]
+        border: ContainerBorder,
+        border_fg: Option<Color>,
+        title: Option<String>,
+        #[serde(default = "LabelAlignment::default")]
+        title_align: LabelAlignment,
+        #[serde(default = "Spacing::default")]
+        padding: Spacing,
+        #[serde(default = "Spacing::default")]
+        margin: Spacing,
+        #[serde(flatten)]
+        style: TextStyle,
+        #[serde(flatten)]
+        mouse: MouseBindings,
     },
     #[serde(rename = "cover-art")]
     CoverArt {
-        width: Option<u16>,
-        height: Option<u16>,
+        width: Option<Size>,
+        height: Option<Size>,
         #[serde(default = "CoverArtResize::default")]
         resize: CoverArtResize,
         bg: Option<Color>,
         fg: Option<Color>,
+        #[serde(flatten)]
+        style: TextStyle,
+        #[serde(flatten)]
+        mouse: MouseBindings,
     },
     Label {
+        #[serde(default = "generate_btn_id")]
+        id: String,
         text: String,
+        size: Option<Size>,
         #[serde(default = "LabelAlignment::default")]
         align: LabelAlignment,
         #[serde(default = "default_truncate")]
         truncate: bool,
+        #[serde(default = "TruncatePosition::default")]
+        truncate_position: TruncatePosition,
+        #[serde(default = "default_ellipsis")]
+        ellipsis: String,
+        /// Scrolls the text when it doesn't fit instead of truncating it.
+        #[serde(default)]
+        scroll: bool,
+        /// Cells per second.
+        #[serde(default = "default_scroll_speed")]
+        scroll_speed: f64,
+        /// Milliseconds to wait at the start of the text and once its end is visible.
+        #[serde(default = "default_scroll_pause")]
+        scroll_pause: u64,
+        #[serde(default = "default_scroll_separator")]
+        scroll_separator: String,
         bg: Option<Color>,
         fg: Option<Color>,
+        #[serde(flatten)]
+        style: TextStyle,
+        #[serde(flatten)]
+        mouse: MouseBindings,
     },
     Button {
         #[serde(default = "generate_btn_id")]
         id: String,
         text: String,
+        size: Option<Size>,
         action: Option<Action>,
         exec: Option<String>,
         bg: Option<Color>,
         fg: Option<Color>,
+        #[serde(flatten)]
+        style: TextStyle,
+        #[serde(flatten)]
+        mouse: MouseBindings,
     },
     Progress {
-        size: Option<u16>,
+        #[serde(default = "generate_btn_id")]
+        id: String,
+        size: Option<Size>,
         progress: ProgressOption,
         empty: ProgressOption,
+        #[serde(flatten)]
+        style: TextStyle,
+        #[serde(flatten)]
+        mouse: MouseBindings,
     },
     Empty {
-        size: u16,
+        size: Size,
+        bg: Option<Color>,
+        fg: Option<Color>,
+        #[serde(flatten)]
+        style: TextStyle,
+    },
+    Volume {
+        #[serde(default = "generate_btn_id")]
+        id: String,
+        size: Option<Size>,
+        #[serde(default = "default_volume_step")]
+        step: i64,
+        progress: ProgressOption,
+        empty: ProgressOption,
+        #[serde(flatten)]
+        style: TextStyle,
+        #[serde(flatten)]
+        mouse: MouseBindings,
+    },
+    #[serde(rename = "player-list")]
+    PlayerList {
+        #[serde(default = "generate_btn_id")]
+        id: String,
+        width: Option<Size>,
+        height: Option<Size>,
         bg: Option<Color>,
         fg: Option<Color>,
+        active_bg: Option<Color>,
+        active_fg: Option<Color>,
+        #[serde(flatten)]
+        style: TextStyle,
     },
 }
 
@@ -174,55 +530,154 @@ impl StatefulWidget for &FumWidget {
         Self: Sized,
     {
         match self {
-            FumWidget::Container { .. } => container::render(&self, area, buf, state),
-            FumWidget::CoverArt { .. } => cover_art::render(&self, area, buf, state),
-            FumWidget::Label { .. } => label::render(&self, area, buf, state),
-            FumWidget::Button { .. } => button::render(&self, area, buf, state),
-            FumWidget::Progress { .. } => progress::render(&self, area, buf, state),
-            FumWidget::Empty { .. } => empty::render(&self, area, buf, state),
+            FumWidget::Container { .. } => container::render(self, area, buf, state),
+            FumWidget::CoverArt { .. } => cover_art::render(self, area, buf, state),
+            FumWidget::Label { .. } => label::render(self, area, buf, state),
+            FumWidget::Button { .. } => button::render(self, area, buf, state),
+            FumWidget::Progress { .. } => progress::render(self, area, buf, state),
+            FumWidget::Empty { .. } => empty::render(self, area, buf, state),
+            FumWidget::Volume { .. } => volume::render(self, area, buf, state),
+            FumWidget::PlayerList { .. } => player_list::render(self, area, buf, state),
         }
     }
 }
 
 impl FumWidget {
+    /// Every text of this widget and its children that may contain variables.
+    pub fn texts(&self) -> Vec<&str> {
+        match self {
+            Self::Container { children, .. } => {
+                children.iter().flat_map(|child| child.texts()).collect()
+            }
+            Self::Label { text, .. } | Self::Button { text, .. } => vec![text.as_str()],
+            _ => Vec::new(),
+        }
+    }
+
     pub fn get_size(&self, state: &mut FumState) -> Constraint {
+        let size = |size: &Option<Size>, default: Constraint| {
+            size.map(Size::to_constraint).unwrap_or(default)
+        };
+
         match self {
-            Self::Container {
-                width,
-                height,
-                direction,
-                ..
-            } => match direction {
-                Direction::Horizontal => width
-                    .map(|w| Constraint::Length(w))
-                    .unwrap_or(Constraint::Min(0)),
-                Direction::Vertical => height
-                    .map(|h| Constraint::Length(h))
-                    .unwrap_or(Constraint::Min(0)),
+            // The parent's layout sizes the main axis, the container only its cross one
+            Self::Container { width, height, .. } => match &state.parent_direction {
+                Direction::Horizontal => size(width, Constraint::Min(0)),
+                Direction::Vertical => size(height, Constraint::Min(0)),
             },
             Self::CoverArt { width, height, .. } => match &state.parent_direction {
-                Direction::Horizontal => width
-                    .map(|w| Constraint::Length(w))
-                    .unwrap_or(Constraint::Min(0)),
-                Direction::Vertical => height
-                    .map(|h| Constraint::Length(h))
-                    .unwrap_or(Constraint::Min(0)),
+                Direction::Horizontal => size(width, Constraint::Min(0)),
+                Direction::Vertical => size(height, Constraint::Min(0)),
+            },
+            Self::Label {
+                size: label_size, ..
+            } => match &state.parent_direction {
+                Direction::Horizontal => size(label_size, Constraint::Min(0)),
+                Direction::Vertical => size(label_size, Constraint::Length(1)),
             },
-            Self::Label { .. } => match &state.parent_direction {
-                Direction::Horizontal => Constraint::Min(0),
-                Direction::Vertical => Constraint::Length(1),
+            Self::Button {
+                text,
+                size: button_size,
+                ..
+            } => match (&state.parent_direction, button_size) {
+                (_, Some(button_size)) => button_size.to_constraint(),
+                (Direction::Horizontal, None) => {
+                    Constraint::Length(line_width(&replace_markup(text, state)) as u16)
+                }
+                (Direction::Vertical, None) => Constraint::Length(1),
             },
-            Self::Button { text, .. } => match &state.parent_direction {
-                Direction::Horizontal => Constraint::Length(replace_text(text, state).len() as u16),
-                Direction::Vertical => Constraint::Length(1),
+            Self::Progress {
+                size: progress_size,
+                ..
+            }
+            | Self::Volume {
+                size: progress_size,
+                ..
+            } => match &state.parent_direction {
+                Direction::Horizontal => size(progress_size, Constraint::Min(0)),
+                Direction::Vertical => size(progress_size, Constraint::Length(1)),
             },
-            Self::Progress { size, .. } => match &state.parent_direction {
-                Direction::Horizontal => size
-                    .map(|s| Constraint::Length(s))
-                    .unwrap_or(Constraint::Min(0)),
-                Direction::Vertical => Constraint::Length(1),
+            Self::Empty { size, .. } => size.to_constraint(),
+            Self::PlayerList { width, height, .. } => match &state.parent_direction {
+                Direction::Horizontal => size(width, Constraint::Min(0)),
+                Direction::Vertical => size(
+                    height,
+                    Constraint::Length(state.players.len().max(1) as u16),
+                ),
             },
-            Self::Empty { size, .. } => Constraint::Length(*size),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::meta::Meta;
+
+    fn size(value: serde_json::Value) -> Result<Size, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(Size::parse("12"), Some(Size::Length(12)));
+        assert_eq!(Size::parse(" 40 % "), Some(Size::Percentage(40)));
+        assert_eq!(Size::parse("1 / 3"), Some(Size::Ratio(1, 3)));
+        assert_eq!(Size::parse("min:10"), Some(Size::Min(10)));
+        assert_eq!(Size::parse("max: 30"), Some(Size::Max(30)));
+        assert_eq!(Size::parse("fill:2"), Some(Size::Fill(2)));
+
+        assert_eq!(Size::parse(""), None);
+        assert_eq!(Size::parse("-1"), None);
+        assert_eq!(Size::parse("70000"), None);
+        assert_eq!(Size::parse("%"), None);
+        assert_eq!(Size::parse("1/0"), None);
+        assert_eq!(Size::parse("1/"), None);
+        assert_eq!(Size::parse("min:"), None);
+        assert_eq!(Size::parse("grow:2"), None);
+        assert_eq!(Size::parse("wide"), None);
+    }
+
+    #[test]
+    fn test_container_size_follows_parent_direction() {
+        let column: FumWidget = serde_json::from_value(serde_json::json!({
+            "type": "container",
+            "direction": "vertical",
+            "width": "40%",
+            "height": 3,
+            "children": []
+        }))
+        .unwrap();
+
+        let row: FumWidget = serde_json::from_value(serde_json::json!({
+            "type": "container",
+            "direction": "horizontal",
+            "width": 10,
+            "height": "min:2",
+            "children": []
+        }))
+        .unwrap();
+
+        let mut state = FumState::new(Meta::default());
+
+        state.parent_direction = Direction::Horizontal;
+        assert_eq!(column.get_size(&mut state), Constraint::Percentage(40));
+        assert_eq!(row.get_size(&mut state), Constraint::Length(10));
+
+        state.parent_direction = Direction::Vertical;
+        assert_eq!(column.get_size(&mut state), Constraint::Length(3));
+        assert_eq!(row.get_size(&mut state), Constraint::Min(2));
+    }
+
+    #[test]
+    fn test_deserialize_size() {
+        assert_eq!(size(serde_json::json!(12)).unwrap(), Size::Length(12));
+        assert_eq!(size(serde_json::json!("1/3")).unwrap(), Size::Ratio(1, 3));
+
+        assert!(size(serde_json::json!(-1)).is_err());
+        assert!(size(serde_json::json!(70000)).is_err());
+        assert!(size(serde_json::json!(1.5)).is_err());
+        assert!(size(serde_json::json!("wide")).is_err());
+        assert!(size(serde_json::json!(null)).is_err());
+    }
+}
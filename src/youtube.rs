@@ -334,9 +334,6 @@ impl YouTubeClient {
                                 .send(resp)
                                 .expect("failed to send youtube server response through channel");
                         }
-                        _ => {
-                            panic!("unexpected variant of YouTubeAction")
-                        }
                     }
                 }
             })